@@ -1,20 +1,417 @@
-use std::{fs::File, io::BufWriter, path::Path};
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::upper_case_acronyms)]
 
-type RGB = [u8; 3];
+use core::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+#[cfg(feature = "std")]
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    str::FromStr,
+};
 
-pub fn rgb(v: u32) -> RGB {
+// the float methods the color math needs, backed by libm without std
+#[cfg(not(feature = "std"))]
+trait FloatExt {
+    fn powf(self, n: Self) -> Self;
+    fn powi(self, n: i32) -> Self;
+    fn cbrt(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn round(self) -> Self;
+    fn ln(self) -> Self;
+    fn exp(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn atan2(self, x: Self) -> Self;
+}
+
+#[cfg(not(feature = "std"))]
+impl FloatExt for f32 {
+    fn powf(self, n: f32) -> f32 {
+        libm::powf(self, n)
+    }
+    fn powi(self, n: i32) -> f32 {
+        libm::powf(self, n as f32)
+    }
+    fn cbrt(self) -> f32 {
+        libm::cbrtf(self)
+    }
+    fn sqrt(self) -> f32 {
+        libm::sqrtf(self)
+    }
+    fn round(self) -> f32 {
+        libm::roundf(self)
+    }
+    fn ln(self) -> f32 {
+        libm::logf(self)
+    }
+    fn exp(self) -> f32 {
+        libm::expf(self)
+    }
+    fn sin(self) -> f32 {
+        libm::sinf(self)
+    }
+    fn cos(self) -> f32 {
+        libm::cosf(self)
+    }
+    fn atan2(self, x: f32) -> f32 {
+        libm::atan2f(self, x)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl FloatExt for f64 {
+    fn powf(self, n: f64) -> f64 {
+        libm::pow(self, n)
+    }
+    fn powi(self, n: i32) -> f64 {
+        libm::pow(self, n as f64)
+    }
+    fn cbrt(self) -> f64 {
+        libm::cbrt(self)
+    }
+    fn sqrt(self) -> f64 {
+        libm::sqrt(self)
+    }
+    fn round(self) -> f64 {
+        libm::round(self)
+    }
+    fn ln(self) -> f64 {
+        libm::log(self)
+    }
+    fn exp(self) -> f64 {
+        libm::exp(self)
+    }
+    fn sin(self) -> f64 {
+        libm::sin(self)
+    }
+    fn cos(self) -> f64 {
+        libm::cos(self)
+    }
+    fn atan2(self, x: f64) -> f64 {
+        libm::atan2(self, x)
+    }
+}
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct RGB {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl RGB {
+    pub const BLACK: RGB = RGB::new(0, 0, 0);
+    pub const WHITE: RGB = RGB::new(255, 255, 255);
+    pub const RED: RGB = RGB::new(255, 0, 0);
+    pub const GREEN: RGB = RGB::new(0, 255, 0);
+    pub const BLUE: RGB = RGB::new(0, 0, 255);
+    pub const YELLOW: RGB = RGB::new(255, 255, 0);
+    pub const CYAN: RGB = RGB::new(0, 255, 255);
+    pub const MAGENTA: RGB = RGB::new(255, 0, 255);
+
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    pub fn from_hwb(hwb: HWB) -> RGB {
+        hwb_to_rgb(hwb)
+    }
+
+    pub fn to_hwb(&self) -> HWB {
+        rgb_to_hwb(*self)
+    }
+
+    pub fn from_hsl(hsl: HSL) -> RGB {
+        hsl_to_rgb(hsl)
+    }
+
+    pub fn to_hsl(&self) -> HSL {
+        rgb_to_hsl(*self)
+    }
+
+    pub fn from_hsv(hsv: HSV) -> RGB {
+        hsv_to_rgb(hsv)
+    }
+
+    pub fn to_hsv(&self) -> HSV {
+        rgb_to_hsv(*self)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn to_hex(&self, uppercase: bool, with_hash: bool) -> String {
+        let hash = if with_hash { "#" } else { "" };
+        let (r, g, b) = (self.red, self.green, self.blue);
+        if uppercase {
+            format!("{}{:02X}{:02X}{:02X}", hash, r, g, b)
+        } else {
+            format!("{}{:02x}{:02x}{:02x}", hash, r, g, b)
+        }
+    }
+}
+
+impl fmt::Display for RGB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError(String);
+
+#[cfg(feature = "std")]
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color: {:?}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseColorError {}
+
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ColorError {
+    Io(std::io::Error),
+    Parse(String),
+    Png(png::EncodingError),
+    #[cfg(feature = "gif")]
+    Gif(gif::EncodingError),
+    SizeMismatch {
+        expected: (u32, u32),
+        found: (u32, u32),
+    },
+    // bytes in a row passed to PngRowWriter
+    RowLength {
+        expected: usize,
+        found: usize,
+    },
+    // rows written to a PngRowWriter
+    RowCount {
+        expected: u32,
+        found: u32,
+    },
+    // dimensions the output format can't store
+    TooLarge {
+        width: u32,
+        height: u32,
+    },
+    NoFrames,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorError::Io(err) => write!(f, "{}", err),
+            ColorError::Parse(s) => write!(f, "invalid color: {:?}", s),
+            ColorError::Png(err) => write!(f, "{}", err),
+            #[cfg(feature = "gif")]
+            ColorError::Gif(err) => write!(f, "{}", err),
+            ColorError::SizeMismatch { expected, found } => write!(
+                f,
+                "image is {}x{}, expected {}x{}",
+                found.0, found.1, expected.0, expected.1
+            ),
+            ColorError::RowLength { expected, found } => {
+                write!(f, "row is {} bytes, expected {}", found, expected)
+            }
+            ColorError::RowCount { expected, found } => {
+                write!(f, "got {} rows, expected {}", found, expected)
+            }
+            ColorError::TooLarge { width, height } => {
+                write!(f, "image of {}x{} is too large", width, height)
+            }
+            ColorError::NoFrames => write!(f, "no frames"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ColorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ColorError::Io(err) => Some(err),
+            ColorError::Png(err) => Some(err),
+            #[cfg(feature = "gif")]
+            ColorError::Gif(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for ColorError {
+    fn from(err: std::io::Error) -> Self {
+        ColorError::Io(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<png::EncodingError> for ColorError {
+    fn from(err: png::EncodingError) -> Self {
+        ColorError::Png(err)
+    }
+}
+
+#[cfg(feature = "gif")]
+impl From<gif::EncodingError> for ColorError {
+    fn from(err: gif::EncodingError) -> Self {
+        ColorError::Gif(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ParseColorError> for ColorError {
+    fn from(err: ParseColorError) -> Self {
+        ColorError::Parse(err.0)
+    }
+}
+
+// accepts #rrggbb and #rgb, the # is optional
+#[cfg(feature = "std")]
+impl FromStr for RGB {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        if hex.len() != 3 && hex.len() != 6 {
+            return Err(ParseColorError(s.to_string()));
+        }
+        let [r, g, b, _] = parse_hex_rgba(s)?;
+        Ok(RGB::new(r, g, b))
+    }
+}
+
+// also accepts #rrggbbaa and #rgba, colors without alpha are opaque
+#[cfg(feature = "std")]
+pub fn parse_hex_rgba(s: &str) -> Result<[u8; 4], ParseColorError> {
+    let err = || ParseColorError(s.to_string());
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return Err(err());
+    }
+    let v = u32::from_str_radix(hex, 16).map_err(|_| err())?;
+    let nibble = |shift: u32| ((v >> shift) & 0xf) as u8 * 0x11;
+    let byte = |shift: u32| (v >> shift) as u8;
+    match hex.len() {
+        3 => Ok([nibble(8), nibble(4), nibble(0), 255]),
+        4 => Ok([nibble(12), nibble(8), nibble(4), nibble(0)]),
+        6 => Ok([byte(16), byte(8), byte(0), 255]),
+        8 => Ok([byte(24), byte(16), byte(8), byte(0)]),
+        _ => Err(err()),
+    }
+}
+
+// css hwb(h w% b%) with space or comma separators, hue in degrees with an optional deg
+#[cfg(feature = "std")]
+pub fn parse_css_hwb(s: &str) -> Result<HWB, ParseColorError> {
+    let err = || ParseColorError(s.to_string());
+    // css function names and units are case-insensitive
+    let lower = s.trim().to_ascii_lowercase();
+    let args = lower
+        .strip_prefix("hwb(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(err)?;
+    let parts: Vec<&str> = args
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .collect();
+    let [hue, white, black] = parts[..] else {
+        return Err(err());
+    };
+    let number = |v: &str| v.parse::<f32>().ok().filter(|v| v.is_finite());
+    let hue = number(hue.strip_suffix("deg").unwrap_or(hue)).ok_or_else(err)?;
+    let percent = |v: &str| {
+        let v = number(v.strip_suffix('%')?)?;
+        Some((v * 10.0).round().clamp(0.0, 1000.0) as u16)
+    };
+    let hue = ((hue * 10.0).round() as i32).rem_euclid(3600) as u32;
+    Ok((
+        hue,
+        percent(white).ok_or_else(err)?,
+        percent(black).ok_or_else(err)?,
+    ))
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for RGB {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for RGB {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// unpacks 0xRRGGBB
+impl From<u32> for RGB {
+    fn from(v: u32) -> Self {
+        rgb(v)
+    }
+}
+
+impl From<[u8; 3]> for RGB {
+    fn from(c: [u8; 3]) -> Self {
+        Self::new(c[0], c[1], c[2])
+    }
+}
+
+impl From<RGB> for [u8; 3] {
+    fn from(rgb: RGB) -> Self {
+        [rgb.red, rgb.green, rgb.blue]
+    }
+}
+
+// channels by index in red, green, blue order
+impl Index<usize> for RGB {
+    type Output = u8;
+
+    fn index(&self, i: usize) -> &u8 {
+        match i {
+            0 => &self.red,
+            1 => &self.green,
+            2 => &self.blue,
+            _ => panic!("channel index out of range: {}", i),
+        }
+    }
+}
+
+impl IndexMut<usize> for RGB {
+    fn index_mut(&mut self, i: usize) -> &mut u8 {
+        match i {
+            0 => &mut self.red,
+            1 => &mut self.green,
+            2 => &mut self.blue,
+            _ => panic!("channel index out of range: {}", i),
+        }
+    }
+}
+
+pub const fn rgb(v: u32) -> RGB {
     let r = (v & 0xff0000) >> 16;
     let g = (v & 0xff00) >> 8;
-    let b = (v & 0xff) >> 0;
-    [r as u8, g as u8, b as u8]
+    let b = v & 0xff;
+    RGB::new(r as u8, g as u8, b as u8)
 }
 
 fn min(rgb: RGB) -> u8 {
-    rgb[0].min(rgb[1]).min(rgb[2])
+    rgb.red.min(rgb.green).min(rgb.blue)
 }
 
 fn max(rgb: RGB) -> u8 {
-    rgb[0].max(rgb[1]).max(rgb[2])
+    rgb.red.max(rgb.green).max(rgb.blue)
 }
 
 // hue in the range: 0-3600
@@ -26,12 +423,12 @@ pub fn hue_to_rgb(hue: u32) -> RGB {
     let x = (hue % 600 * 255 / 600) as u8;
     let y = 255 - x;
     match h as u8 % 6 {
-        0 => [255, x, 0],
-        1 => [y, 255, 0],
-        2 => [0, 255, x],
-        3 => [0, y, 255],
-        4 => [x, 0, 255],
-        5 => [255, 0, y],
+        0 => RGB::new(255, x, 0),
+        1 => RGB::new(y, 255, 0),
+        2 => RGB::new(0, 255, x),
+        3 => RGB::new(0, y, 255),
+        4 => RGB::new(x, 0, 255),
+        5 => RGB::new(255, 0, y),
         _ => unreachable!(),
     }
 }
@@ -48,15 +445,49 @@ pub fn rgb_to_hue(rgb: RGB) -> u16 {
 
 pub fn gray(value: u16) -> RGB {
     let value = (255 * value as u32 / 1000) as u8;
-    [value, value, value]
+    RGB::new(value, value, value)
 }
 
 pub fn mix(p: u16, a: RGB, b: RGB) -> RGB {
-    let mut out: RGB = RGB::default();
+    let p = p.min(1000);
+    let mut out = RGB::default();
     for i in 0..=2 {
         let start = (a[i] as i32) * 1000;
         let delta = (b[i] as i32 - a[i] as i32) * (p as i32);
-        out[i] = ((start + delta) / 1000) as u8;
+        out[i] = ((start + delta + 500) / 1000) as u8;
+    }
+    out
+}
+
+pub fn mix_f(t: f32, a: RGB, b: RGB) -> RGB {
+    let t = t.clamp(0.0, 1.0);
+    let mut out = RGB::default();
+    for i in 0..=2 {
+        let start = a[i] as f32;
+        out[i] = f32_to_u8_clamped(start + (b[i] as f32 - start) * t);
+    }
+    out
+}
+
+// like mix_f but keeps 16 bits per channel, for Pixels16
+pub fn mix_f16(t: f32, a: RGB, b: RGB) -> [u16; 3] {
+    let t = t.clamp(0.0, 1.0);
+    let mut out = [0; 3];
+    for (i, c) in out.iter_mut().enumerate() {
+        let start = a[i] as f32 * 257.0;
+        *c = (start + (b[i] as f32 * 257.0 - start) * t).round() as u16;
+    }
+    out
+}
+
+// interpolates in linear light instead of raw sRGB values
+pub fn mix_linear(p: u16, a: RGB, b: RGB) -> RGB {
+    let t = p.min(1000) as f32 / 1000.0;
+    let mut out = RGB::default();
+    for i in 0..=2 {
+        let start = srgb_to_linear(a[i]);
+        let end = srgb_to_linear(b[i]);
+        out[i] = linear_to_srgb(start + (end - start) * t);
     }
     out
 }
@@ -67,7 +498,7 @@ pub fn hwb_to_rgb(hwb: HWB) -> RGB {
         let w = 1000 * hwb.1 as u32 / v as u32;
         return gray(w as u16);
     }
-    let hue = hwb.0;
+    let hue = hwb.0 % 3600;
     let w = (255 * hwb.1 as u32 / 1000) as u8;
     let b = (255 * hwb.2 as u32 / 1000) as u8;
     let v = 255 - b;
@@ -77,20 +508,24 @@ pub fn hwb_to_rgb(hwb: HWB) -> RGB {
     if h & 1 == 1 {
         x = 1000 - x
     }
-    let x = w + (x as i32 * (v as i32 - w as i32) / 1000) as u8;
+    let x = w + (x * (v as i32 - w as i32) / 1000) as u8;
     match h as u8 % 6 {
-        0 => [v, x, w],
-        1 => [x, v, w],
-        2 => [w, v, x],
-        3 => [w, x, v],
-        4 => [x, w, v],
-        5 => [v, w, x],
+        0 => RGB::new(v, x, w),
+        1 => RGB::new(x, v, w),
+        2 => RGB::new(w, v, x),
+        3 => RGB::new(w, x, v),
+        4 => RGB::new(x, w, v),
+        5 => RGB::new(v, w, x),
         _ => unreachable!(),
     }
 }
 
 pub fn rgb_to_hwb(rgb: RGB) -> HWB {
-    let &[r, g, b] = &rgb;
+    let RGB {
+        red: r,
+        green: g,
+        blue: b,
+    } = rgb;
     let w = min(rgb);
     let v = max(rgb);
     let black = 255 - v;
@@ -102,8 +537,7 @@ pub fn rgb_to_hwb(rgb: RGB) -> HWB {
             b as i32 - r as i32
         } else {
             r as i32 - g as i32
-        } as i32
-            * 1000
+        } * 1000
             / 256;
         let d = (v as i32 - w as i32) * 1000 / 256;
         hue = if r == v {
@@ -112,8 +546,7 @@ pub fn rgb_to_hwb(rgb: RGB) -> HWB {
             2
         } else {
             4
-        } as i32
-            * 600;
+        } * 600;
         hue %= 3600;
         hue += 600 * f / d;
         hue += 3600;
@@ -126,213 +559,5023 @@ pub fn rgb_to_hwb(rgb: RGB) -> HWB {
     )
 }
 
-pub struct Pixels {
-    width: u32,
-    height: u32,
-    data: Vec<u8>,
+// hue in the range: 0-3600
+// saturation and lightness: 0-1000
+type HSL = (u32, u16, u16);
+
+pub fn rgb_to_hsl(rgb: RGB) -> HSL {
+    let c_min = min(rgb) as i32;
+    let c_max = max(rgb) as i32;
+    let sum = c_max + c_min;
+    let delta = c_max - c_min;
+    let l = sum * 1000 / 510;
+    let s = if delta == 0 {
+        0
+    } else {
+        delta * 1000 / (255 - (sum - 255).abs())
+    };
+    (rgb_to_hwb(rgb).0, s as u16, l as u16)
 }
 
-impl Pixels {
-    pub fn new(width: u32, height: u32) -> Self {
-        let size = (width * height * 3) as usize;
-        let data = vec![255; size];
-        Self {
-            width,
-            height,
-            data,
-        }
-    }
+pub fn hsl_to_rgb(hsl: HSL) -> RGB {
+    let s = hsl.1.min(1000) as i32;
+    let l = hsl.2.min(1000) as i32;
+    let c = (1000 - (2 * l - 1000).abs()) * s / 1000;
+    let w = l - c / 2;
+    let b = 1000 - (l + c - c / 2);
+    hwb_to_rgb((hsl.0, w as u16, b as u16))
+}
 
-    pub fn save_image(&self, name: &str) {
-        let path = Path::new(name);
-        let file = File::create(path).unwrap();
-        let ref mut w = BufWriter::new(file);
+// hue in the range: 0-3600
+// saturation and value: 0-1000
+type HSV = (u32, u16, u16);
 
-        let mut encoder = png::Encoder::new(w, self.width, self.height);
-        encoder.set_color(png::ColorType::RGB);
-        encoder.set_depth(png::BitDepth::Eight);
-        let mut writer = encoder.write_header().unwrap();
+pub fn rgb_to_hsv(rgb: RGB) -> HSV {
+    let c_min = min(rgb) as u32;
+    let c_max = max(rgb) as u32;
+    let s = ((c_max - c_min) * 1000).checked_div(c_max).unwrap_or(0);
+    (rgb_to_hwb(rgb).0, s as u16, (c_max * 1000 / 255) as u16)
+}
 
-        writer.write_image_data(&self.data).unwrap();
-    }
+pub fn hsv_to_rgb(hsv: HSV) -> RGB {
+    let s = hsv.1.min(1000) as u32;
+    let v = hsv.2.min(1000) as u32;
+    let w = (1000 - s) * v / 1000;
+    hwb_to_rgb((hsv.0, w as u16, (1000 - v) as u16))
+}
 
-    pub fn rect(&mut self, x: u32, y: u32, w: u32, h: u32, rgb: RGB) {
-        for x in x..=x + w {
-            for y in y..=y + h {
-                self.set(x, y, rgb);
-            }
+// thin wrappers around the tuple models that print in css-like notation
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Hwb(pub u32, pub u16, pub u16);
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Hsl(pub u32, pub u16, pub u16);
+
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Hsv(pub u32, pub u16, pub u16);
+
+// prints a tenths fixed-point value, dropping a zero fraction
+struct Tenths(u32);
+
+impl fmt::Display for Tenths {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 % 10 {
+            0 => write!(f, "{}", self.0 / 10),
+            frac => write!(f, "{}.{}", self.0 / 10, frac),
         }
     }
+}
 
-    pub fn set(&mut self, x: u32, y: u32, rgb: RGB) {
-        let index = ((y * self.width + x) * 3) as usize;
-        self.data[index] = rgb[0];
-        self.data[index + 1] = rgb[1];
-        self.data[index + 2] = rgb[2];
+impl fmt::Display for Hwb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hwb({} {}% {}%)",
+            Tenths(self.0),
+            Tenths(self.1 as u32),
+            Tenths(self.2 as u32)
+        )
     }
 }
 
-pub fn palette(color: HWB) {
-    const SCALE: usize = 4;
-    const SIZE: u32 = 1 << SCALE;
-
-    const STEPS: usize = 8;
-
-    let width = STEPS << SCALE;
-    let height = STEPS << SCALE;
-    let mut pixels = Pixels::new(width as u32, height as u32);
-    for w in 0..STEPS {
-        for b in 0..STEPS {
-            let x = (b as u32) << SCALE;
-            let y = (w as u32) << SCALE;
-            let w = (1000 * w) / (STEPS - 1);
-            let b = (1000 * b) / (STEPS - 1);
-            let rgb = hwb_to_rgb((color.0, w as u16, b as u16));
-            pixels.rect(x, y, SIZE - 1, SIZE - 1, rgb);
-        }
+impl fmt::Display for Hsl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hsl({} {}% {}%)",
+            Tenths(self.0),
+            Tenths(self.1 as u32),
+            Tenths(self.2 as u32)
+        )
     }
-    let name = format!("images/palette{}.png", color.0 / 10);
-    pixels.save_image(&name);
 }
 
-pub fn hue_palette() {
-    const SCALE: usize = 4;
-    const SIZE: u32 = 1 << SCALE;
+impl fmt::Display for Hsv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "hsv({} {}% {}%)",
+            Tenths(self.0),
+            Tenths(self.1 as u32),
+            Tenths(self.2 as u32)
+        )
+    }
+}
 
-    const WIDTH_STEPS: u32 = 360 / 15;
-    const HEIGHT_STEPS: u32 = 200 / 20;
+impl From<HWB> for Hwb {
+    fn from((h, w, b): HWB) -> Self {
+        Hwb(h, w, b)
+    }
+}
 
-    let mut pixels = Pixels::new(WIDTH_STEPS << SCALE as u32, HEIGHT_STEPS << SCALE as u32);
-    for hue in (0..360).step_by(15) {
-        for value in (0..200).step_by(20) {
-            let x = ((hue / 15) << SCALE) as u32;
-            let y = ((value / 20) << SCALE) as u32;
-            let b = 100 - (value + 10).min(100);
-            let w = (value as i16 - 100).max(0);
-            let rgb = hwb_to_rgb((hue * 10 as u32, w as u16 * 10, b as u16 * 10));
-            pixels.rect(x, y, SIZE - 1, SIZE - 1, rgb);
-        }
+impl From<Hwb> for HWB {
+    fn from(hwb: Hwb) -> Self {
+        (hwb.0, hwb.1, hwb.2)
     }
-    pixels.save_image(&"images/hue_palette.png");
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl From<HSL> for Hsl {
+    fn from((h, s, l): HSL) -> Self {
+        Hsl(h, s, l)
+    }
+}
 
-    #[test]
-    fn test_convert() {
-        assert_eq!(rgb_to_hwb(rgb(0xff0000)), (0, 0, 0));
-        assert_eq!(rgb_to_hwb(rgb(0xff8000)), (301, 0, 0));
-        assert_eq!(rgb_to_hwb(rgb(0x00ff00)), (1200, 0, 0));
-        assert_eq!(rgb_to_hwb(rgb(0x0000ff)), (2400, 0, 0));
-        assert_eq!(rgb_to_hwb(rgb(0xffff00)), (600, 0, 0));
-        assert_eq!(rgb_to_hwb(rgb(0x00ffff)), (1800, 0, 0));
-        assert_eq!(rgb_to_hwb(rgb(0xff00ff)), (3000, 0, 0));
+impl From<Hsl> for HSL {
+    fn from(hsl: Hsl) -> Self {
+        (hsl.0, hsl.1, hsl.2)
+    }
+}
 
-        assert_eq!(rgb_to_hwb(rgb(0xcc3333)), (0, 200, 200));
-        assert_eq!(rgb_to_hwb(rgb(0x808080)), (0, 501, 498));
+impl From<HSV> for Hsv {
+    fn from((h, s, v): HSV) -> Self {
+        Hsv(h, s, v)
     }
+}
 
-    #[test]
-    fn test_hue_to_rgb() {
-        assert_eq!(hue_to_rgb(0), rgb(0xff0000));
-        assert_eq!(hue_to_rgb(600), rgb(0xffff00));
-        assert_eq!(hue_to_rgb(1200), rgb(0x00ff00));
-        assert_eq!(hue_to_rgb(1800), rgb(0x00ffff));
-        assert_eq!(hue_to_rgb(2400), rgb(0x0000ff));
-        assert_eq!(hue_to_rgb(3000), rgb(0xff00ff));
+impl From<Hsv> for HSV {
+    fn from(hsv: Hsv) -> Self {
+        (hsv.0, hsv.1, hsv.2)
     }
+}
 
-    #[test]
-    fn test_hwb_block_red() {
-        assert_eq!(hwb_to_rgb((0, 0, 0)), rgb(0xff0000));
-        assert_eq!(hwb_to_rgb((0, 200, 0)), rgb(0xff3333));
-        assert_eq!(hwb_to_rgb((0, 400, 0)), rgb(0xff6666));
-        assert_eq!(hwb_to_rgb((0, 600, 0)), rgb(0xff9999));
-        assert_eq!(hwb_to_rgb((0, 800, 0)), rgb(0xffcccc));
-        assert_eq!(hwb_to_rgb((0, 1000, 0)), rgb(0xffffff));
+pub fn lighten(rgb: RGB, amount: u16) -> RGB {
+    let (h, s, l) = rgb_to_hsl(rgb);
+    hsl_to_rgb((h, s, (l + amount.min(1000)).min(1000)))
+}
 
-        assert_eq!(hwb_to_rgb((0, 0, 0)), rgb(0xff0000));
-        assert_eq!(hwb_to_rgb((0, 0, 200)), rgb(0xcc0000));
-        assert_eq!(hwb_to_rgb((0, 0, 400)), rgb(0x990000));
-        assert_eq!(hwb_to_rgb((0, 0, 600)), rgb(0x660000));
-        assert_eq!(hwb_to_rgb((0, 0, 800)), rgb(0x330000));
-        assert_eq!(hwb_to_rgb((0, 0, 1000)), rgb(0x000000));
+pub fn darken(rgb: RGB, amount: u16) -> RGB {
+    let (h, s, l) = rgb_to_hsl(rgb);
+    hsl_to_rgb((h, s, l.saturating_sub(amount)))
+}
 
-        assert_eq!(hwb_to_rgb((0, 0, 1000)), rgb(0x000000));
-        assert_eq!(hwb_to_rgb((0, 200, 1000)), rgb(0x2a2a2a));
-        assert_eq!(hwb_to_rgb((0, 400, 1000)), rgb(0x484848));
-        assert_eq!(hwb_to_rgb((0, 600, 1000)), rgb(0x5f5f5f));
-        assert_eq!(hwb_to_rgb((0, 800, 1000)), rgb(0x717171));
-        assert_eq!(hwb_to_rgb((0, 1000, 1000)), rgb(0x7f7f7f));
+pub fn saturate(rgb: RGB, amount: u16) -> RGB {
+    let (h, s, l) = rgb_to_hsl(rgb);
+    hsl_to_rgb((h, (s + amount.min(1000)).min(1000), l))
+}
 
-        assert_eq!(hwb_to_rgb((0, 1000, 1000)), rgb(0x7f7f7f));
-        assert_eq!(hwb_to_rgb((0, 1000, 800)), rgb(0x8d8d8d));
-        assert_eq!(hwb_to_rgb((0, 1000, 600)), rgb(0x9f9f9f));
-        assert_eq!(hwb_to_rgb((0, 1000, 400)), rgb(0xb6b6b6));
-        assert_eq!(hwb_to_rgb((0, 1000, 200)), rgb(0xd4d4d4));
-        assert_eq!(hwb_to_rgb((0, 1000, 0)), rgb(0xffffff));
+pub fn desaturate(rgb: RGB, amount: u16) -> RGB {
+    let (h, s, l) = rgb_to_hsl(rgb);
+    hsl_to_rgb((h, s.saturating_sub(amount), l))
+}
 
-        assert_eq!(hwb_to_rgb((0, 0, 200)), rgb(0xcc0000));
-        assert_eq!(hwb_to_rgb((0, 200, 200)), rgb(0xcc3333));
-        assert_eq!(hwb_to_rgb((0, 400, 200)), rgb(0xcc6666));
-        assert_eq!(hwb_to_rgb((0, 600, 200)), rgb(0xcc9999));
-        assert_eq!(hwb_to_rgb((0, 800, 200)), rgb(0xcccccc));
-        assert_eq!(hwb_to_rgb((0, 1000, 200)), rgb(0xd4d4d4));
+pub fn tint(rgb: RGB, amount: u16) -> RGB {
+    mix(amount, rgb, RGB::WHITE)
+}
 
-        assert_eq!(hwb_to_rgb((0, 0, 400)), rgb(0x990000));
-        assert_eq!(hwb_to_rgb((0, 200, 400)), rgb(0x993333));
-        assert_eq!(hwb_to_rgb((0, 400, 400)), rgb(0x996666));
-        assert_eq!(hwb_to_rgb((0, 600, 400)), rgb(0x999999));
-        assert_eq!(hwb_to_rgb((0, 800, 400)), rgb(0xa9a9a9));
-        assert_eq!(hwb_to_rgb((0, 1000, 400)), rgb(0xb6b6b6));
+pub fn shade(rgb: RGB, amount: u16) -> RGB {
+    mix(amount, rgb, RGB::BLACK)
+}
 
-        assert_eq!(hwb_to_rgb((0, 0, 600)), rgb(0x660000));
+// toward the gray with the same luminance
+pub fn tone(rgb: RGB, amount: u16) -> RGB {
+    let l = luminance(rgb);
+    mix(amount, rgb, RGB::new(l, l, l))
+}
+
+pub fn rotate_hue(rgb: RGB, degrees_tenths: i32) -> RGB {
+    if min(rgb) == max(rgb) {
+        return rgb;
+    }
+    let (hue, w, b) = rgb_to_hwb(rgb);
+    let hue = (hue as i32 + degrees_tenths).rem_euclid(3600);
+    hwb_to_rgb((hue as u32, w, b))
+}
+
+fn offset_hue(hwb: HWB, offset: u32) -> HWB {
+    ((hwb.0 + offset) % 3600, hwb.1, hwb.2)
+}
+
+// the two neighbours of the complement
+pub fn split_complementary(hwb: HWB) -> [HWB; 2] {
+    [offset_hue(hwb, 1500), offset_hue(hwb, 2100)]
+}
+
+pub fn tetradic(hwb: HWB) -> [HWB; 3] {
+    [
+        offset_hue(hwb, 900),
+        offset_hue(hwb, 1800),
+        offset_hue(hwb, 2700),
+    ]
+}
+
+// shades with added black, then the pure hue, then tints with added white,
+// skipping pure black and white
+#[cfg(feature = "std")]
+pub fn monochromatic(hwb: HWB, steps: usize) -> Vec<HWB> {
+    (1..=steps)
+        .map(|i| {
+            let v = (2000 * i / (steps + 1)) as u16;
+            (hwb.0, v.max(1000) - 1000, 1000 - v.min(1000))
+        })
+        .collect()
+}
+
+pub fn invert(rgb: RGB) -> RGB {
+    RGB::new(255 - rgb.red, 255 - rgb.green, 255 - rgb.blue)
+}
+
+pub fn sepia(rgb: RGB) -> RGB {
+    let (r, g, b) = (rgb.red as u32, rgb.green as u32, rgb.blue as u32);
+    let channel = |wr: u32, wg: u32, wb: u32| ((wr * r + wg * g + wb * b) / 1000).min(255) as u8;
+    RGB::new(
+        channel(393, 769, 189),
+        channel(349, 686, 168),
+        channel(272, 534, 131),
+    )
+}
+
+pub fn premultiply(rgba: [u8; 4]) -> [u8; 4] {
+    let a = rgba[3] as u32;
+    let channel = |c: u8| ((c as u32 * a + 127) / 255) as u8;
+    [
+        channel(rgba[0]),
+        channel(rgba[1]),
+        channel(rgba[2]),
+        rgba[3],
+    ]
+}
+
+// fully transparent pixels have no color left and stay black
+pub fn unpremultiply(rgba: [u8; 4]) -> [u8; 4] {
+    let a = rgba[3] as u32;
+    if a == 0 {
+        return [0, 0, 0, 0];
+    }
+    let channel = |c: u8| ((c as u32 * 255 + a / 2) / a).min(255) as u8;
+    [
+        channel(rgba[0]),
+        channel(rgba[1]),
+        channel(rgba[2]),
+        rgba[3],
+    ]
+}
+
+fn blend(top: RGB, bottom: RGB, f: impl Fn(u32, u32) -> u32) -> RGB {
+    let mut out = RGB::default();
+    for i in 0..=2 {
+        out[i] = f(top[i] as u32, bottom[i] as u32) as u8;
+    }
+    out
+}
+
+pub fn blend_multiply(top: RGB, bottom: RGB) -> RGB {
+    blend(top, bottom, |t, b| t * b / 255)
+}
+
+pub fn blend_screen(top: RGB, bottom: RGB) -> RGB {
+    blend(top, bottom, |t, b| 255 - (255 - t) * (255 - b) / 255)
+}
+
+pub fn blend_overlay(top: RGB, bottom: RGB) -> RGB {
+    blend(top, bottom, |t, b| {
+        if b < 128 {
+            2 * t * b / 255
+        } else {
+            255 - 2 * (255 - t) * (255 - b) / 255
+        }
+    })
+}
+
+// rounds to the nearest value with halves rounding down, empty input is black
+pub fn average(colors: &[RGB]) -> RGB {
+    let n = colors.len() as u64;
+    let mut out = RGB::default();
+    if n == 0 {
+        return out;
+    }
+    for i in 0..=2 {
+        let sum: u64 = colors.iter().map(|c| c[i] as u64).sum();
+        out[i] = ((sum + (n - 1) / 2) / n) as u8;
+    }
+    out
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LumaCoeffs {
+    Rec601,
+    Rec709,
+}
+
+impl LumaCoeffs {
+    // weights per channel summing to 10000
+    fn weights(self) -> [u32; 3] {
+        match self {
+            LumaCoeffs::Rec601 => [2990, 5870, 1140],
+            LumaCoeffs::Rec709 => [2126, 7152, 722],
+        }
+    }
+
+    pub fn luma(self, rgb: RGB) -> u8 {
+        let [r, g, b] = self.weights();
+        ((r * rgb.red as u32 + g * rgb.green as u32 + b * rgb.blue as u32) / 10000) as u8
+    }
+}
+
+// perceived brightness with the Rec. 601 weights: 0-255
+pub fn luminance(rgb: RGB) -> u8 {
+    LumaCoeffs::Rec601.luma(rgb)
+}
+
+pub fn to_grayscale(rgb: RGB, coeffs: LumaCoeffs) -> RGB {
+    let value = coeffs.luma(rgb);
+    RGB::new(value, value, value)
+}
+
+// Tanner Helland's blackbody approximation, kelvin is clamped to 1000-40000
+pub fn kelvin_to_rgb(kelvin: u32) -> RGB {
+    let t = kelvin.clamp(1000, 40000) as f64 / 100.0;
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (t - 60.0).powf(-0.1332047592)
+    };
+    let g = if t <= 66.0 {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.0).powf(-0.0755148492)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (t - 10.0).ln() - 305.0447927307
+    };
+    let channel = |v: f64| f32_to_u8_clamped(v as f32);
+    RGB::new(channel(r), channel(g), channel(b))
+}
+
+// piecewise approximation of the visible spectrum (380-780nm), black outside of it
+pub fn wavelength_to_rgb(nm: f32) -> RGB {
+    let (r, g, b) = match nm {
+        w if (380.0..440.0).contains(&w) => ((440.0 - w) / 60.0, 0.0, 1.0),
+        w if (440.0..490.0).contains(&w) => (0.0, (w - 440.0) / 50.0, 1.0),
+        w if (490.0..510.0).contains(&w) => (0.0, 1.0, (510.0 - w) / 20.0),
+        w if (510.0..580.0).contains(&w) => ((w - 510.0) / 70.0, 1.0, 0.0),
+        w if (580.0..645.0).contains(&w) => (1.0, (645.0 - w) / 65.0, 0.0),
+        w if (645.0..=780.0).contains(&w) => (1.0, 0.0, 0.0),
+        _ => return RGB::BLACK,
+    };
+    // intensity falls off near the edges of vision
+    let factor = if nm < 420.0 {
+        0.3 + 0.7 * (nm - 380.0) / 40.0
+    } else if nm > 700.0 {
+        0.3 + 0.7 * (780.0 - nm) / 80.0
+    } else {
+        1.0
+    };
+    let channel = |c: f32| f32_to_u8_clamped((c * factor).powf(0.8) * 255.0);
+    RGB::new(channel(r), channel(g), channel(b))
+}
+
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// rounds a 0.0-255.0 value to a byte, saturating out of range values
+fn f32_to_u8_clamped(v: f32) -> u8 {
+    v.round().clamp(0.0, 255.0) as u8
+}
+
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    f32_to_u8_clamped(c * 255.0)
+}
+
+// CIELAB with a D65 white point: lightness 0-100, a and b roughly -128-128
+type LAB = (f32, f32, f32);
+
+pub fn rgb_to_lab(rgb: RGB) -> LAB {
+    let r = srgb_to_linear(rgb.red);
+    let g = srgb_to_linear(rgb.green);
+    let b = srgb_to_linear(rgb.blue);
+
+    let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+    let y = 0.2126729 * r + 0.7151522 * g + 0.072175 * b;
+    let z = (0.0193339 * r + 0.119192 * g + 0.9503041 * b) / 1.08883;
+
+    let f = |t: f32| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+// Björn Ottosson's OKLab: lightness 0-1, a and b roughly -0.4-0.4
+type OKLAB = (f32, f32, f32);
+
+pub fn rgb_to_oklab(rgb: RGB) -> OKLAB {
+    let r = srgb_to_linear(rgb.red);
+    let g = srgb_to_linear(rgb.green);
+    let b = srgb_to_linear(rgb.blue);
+
+    let l = (0.41222147 * r + 0.53633254 * g + 0.051445993 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+
+    (
+        0.21045426 * l + 0.7936178 * m - 0.004072047 * s,
+        1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+        0.025904037 * l + 0.78277177 * m - 0.80867577 * s,
+    )
+}
+
+// out of gamut results are clamped per channel
+pub fn oklab_to_rgb(lab: OKLAB) -> RGB {
+    let (lightness, a, b) = lab;
+    let l = (lightness + 0.39633778 * a + 0.21580376 * b).powi(3);
+    let m = (lightness - 0.105561346 * a - 0.06385417 * b).powi(3);
+    let s = (lightness - 0.08948418 * a - 1.2914855 * b).powi(3);
+
+    RGB::new(
+        linear_to_srgb(4.0767417 * l - 3.3077116 * m + 0.23096993 * s),
+        linear_to_srgb(-1.268438 * l + 2.6097574 * m - 0.3413194 * s),
+        linear_to_srgb(-0.0041960863 * l - 0.7034186 * m + 1.7076147 * s),
+    )
+}
+
+// steps colors from a to b inclusive, evenly spaced in OKLab
+#[cfg(feature = "std")]
+pub fn gradient_ramp_oklab(a: RGB, b: RGB, steps: usize) -> Vec<RGB> {
+    let (a, b) = (rgb_to_oklab(a), rgb_to_oklab(b));
+    let last = steps.saturating_sub(1).max(1) as f32;
+    (0..steps)
+        .map(|i| {
+            let t = i as f32 / last;
+            oklab_to_rgb((
+                a.0 + (b.0 - a.0) * t,
+                a.1 + (b.1 - a.1) * t,
+                a.2 + (b.2 - a.2) * t,
+            ))
+        })
+        .collect()
+}
+
+pub fn delta_e_76(a: RGB, b: RGB) -> f32 {
+    lab_distance(rgb_to_lab(a), rgb_to_lab(b))
+}
+
+fn lab_distance(a: LAB, b: LAB) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2) + (a.2 - b.2).powi(2)).sqrt()
+}
+
+pub fn delta_e_2000(a: RGB, b: RGB) -> f32 {
+    ciede2000(rgb_to_lab(a), rgb_to_lab(b))
+}
+
+// Sharma, Wu and Dalal, "The CIEDE2000 Color-Difference Formula", computed in f64
+fn ciede2000(lab1: LAB, lab2: LAB) -> f32 {
+    let (l1, a1, b1) = (lab1.0 as f64, lab1.1 as f64, lab1.2 as f64);
+    let (l2, a2, b2) = (lab2.0 as f64, lab2.1 as f64, lab2.2 as f64);
+    let pow25_7 = 25f64.powi(7);
+
+    // stretch a* so that neutral colors get hues closer to their chroma
+    let c_bar = ((a1 * a1 + b1 * b1).sqrt() + (a2 * a2 + b2 * b2).sqrt()) / 2.0;
+    let g = 0.5 * (1.0 - (c_bar.powi(7) / (c_bar.powi(7) + pow25_7)).sqrt());
+    let (a1, a2) = ((1.0 + g) * a1, (1.0 + g) * a2);
+    let c1 = (a1 * a1 + b1 * b1).sqrt();
+    let c2 = (a2 * a2 + b2 * b2).sqrt();
+    let hue = |b: f64, a: f64| {
+        let h = if a == 0.0 && b == 0.0 {
+            0.0
+        } else {
+            b.atan2(a).to_degrees()
+        };
+        if h < 0.0 {
+            h + 360.0
+        } else {
+            h
+        }
+    };
+    let (h1, h2) = (hue(b1, a1), hue(b2, a2));
+
+    let dl = l2 - l1;
+    let dc = c2 - c1;
+    let dh = if c1 * c2 == 0.0 {
+        0.0
+    } else if (h2 - h1).abs() <= 180.0 {
+        h2 - h1
+    } else if h2 - h1 > 180.0 {
+        h2 - h1 - 360.0
+    } else {
+        h2 - h1 + 360.0
+    };
+    let dh = 2.0 * (c1 * c2).sqrt() * (dh / 2.0).to_radians().sin();
+
+    let l_bar = (l1 + l2) / 2.0;
+    let c_bar = (c1 + c2) / 2.0;
+    let h_bar = if c1 * c2 == 0.0 {
+        h1 + h2
+    } else if (h1 - h2).abs() <= 180.0 {
+        (h1 + h2) / 2.0
+    } else if h1 + h2 < 360.0 {
+        (h1 + h2 + 360.0) / 2.0
+    } else {
+        (h1 + h2 - 360.0) / 2.0
+    };
+
+    let cos = |deg: f64| deg.to_radians().cos();
+    let t =
+        1.0 - 0.17 * cos(h_bar - 30.0) + 0.24 * cos(2.0 * h_bar) + 0.32 * cos(3.0 * h_bar + 6.0)
+            - 0.20 * cos(4.0 * h_bar - 63.0);
+    let d_theta = 30.0 * (-((h_bar - 275.0) / 25.0).powi(2)).exp();
+    let rc = 2.0 * (c_bar.powi(7) / (c_bar.powi(7) + pow25_7)).sqrt();
+    let sl = 1.0 + 0.015 * (l_bar - 50.0).powi(2) / (20.0 + (l_bar - 50.0).powi(2)).sqrt();
+    let sc = 1.0 + 0.045 * c_bar;
+    let sh = 1.0 + 0.015 * c_bar * t;
+    let rt = -(2.0 * d_theta).to_radians().sin() * rc;
+
+    let (dl, dc, dh) = (dl / sl, dc / sc, dh / sh);
+    (dl * dl + dc * dc + dh * dh + rt * dc * dh).sqrt() as f32
+}
+
+pub fn nearest_color(target: RGB, palette: &[RGB]) -> Option<RGB> {
+    let target = rgb_to_lab(target);
+    palette
+        .iter()
+        .map(|&color| (lab_distance(target, rgb_to_lab(color)), color))
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, color)| color)
+}
+
+// the css named colors, aliases such as cyan and grey follow their first spelling
+pub const CSS_COLORS: [(&str, RGB); 148] = [
+    ("aliceblue", rgb(0xf0f8ff)),
+    ("antiquewhite", rgb(0xfaebd7)),
+    ("aqua", rgb(0x00ffff)),
+    ("aquamarine", rgb(0x7fffd4)),
+    ("azure", rgb(0xf0ffff)),
+    ("beige", rgb(0xf5f5dc)),
+    ("bisque", rgb(0xffe4c4)),
+    ("black", rgb(0x000000)),
+    ("blanchedalmond", rgb(0xffebcd)),
+    ("blue", rgb(0x0000ff)),
+    ("blueviolet", rgb(0x8a2be2)),
+    ("brown", rgb(0xa52a2a)),
+    ("burlywood", rgb(0xdeb887)),
+    ("cadetblue", rgb(0x5f9ea0)),
+    ("chartreuse", rgb(0x7fff00)),
+    ("chocolate", rgb(0xd2691e)),
+    ("coral", rgb(0xff7f50)),
+    ("cornflowerblue", rgb(0x6495ed)),
+    ("cornsilk", rgb(0xfff8dc)),
+    ("crimson", rgb(0xdc143c)),
+    ("cyan", rgb(0x00ffff)),
+    ("darkblue", rgb(0x00008b)),
+    ("darkcyan", rgb(0x008b8b)),
+    ("darkgoldenrod", rgb(0xb8860b)),
+    ("darkgray", rgb(0xa9a9a9)),
+    ("darkgreen", rgb(0x006400)),
+    ("darkgrey", rgb(0xa9a9a9)),
+    ("darkkhaki", rgb(0xbdb76b)),
+    ("darkmagenta", rgb(0x8b008b)),
+    ("darkolivegreen", rgb(0x556b2f)),
+    ("darkorange", rgb(0xff8c00)),
+    ("darkorchid", rgb(0x9932cc)),
+    ("darkred", rgb(0x8b0000)),
+    ("darksalmon", rgb(0xe9967a)),
+    ("darkseagreen", rgb(0x8fbc8f)),
+    ("darkslateblue", rgb(0x483d8b)),
+    ("darkslategray", rgb(0x2f4f4f)),
+    ("darkslategrey", rgb(0x2f4f4f)),
+    ("darkturquoise", rgb(0x00ced1)),
+    ("darkviolet", rgb(0x9400d3)),
+    ("deeppink", rgb(0xff1493)),
+    ("deepskyblue", rgb(0x00bfff)),
+    ("dimgray", rgb(0x696969)),
+    ("dimgrey", rgb(0x696969)),
+    ("dodgerblue", rgb(0x1e90ff)),
+    ("firebrick", rgb(0xb22222)),
+    ("floralwhite", rgb(0xfffaf0)),
+    ("forestgreen", rgb(0x228b22)),
+    ("fuchsia", rgb(0xff00ff)),
+    ("gainsboro", rgb(0xdcdcdc)),
+    ("ghostwhite", rgb(0xf8f8ff)),
+    ("gold", rgb(0xffd700)),
+    ("goldenrod", rgb(0xdaa520)),
+    ("gray", rgb(0x808080)),
+    ("green", rgb(0x008000)),
+    ("greenyellow", rgb(0xadff2f)),
+    ("grey", rgb(0x808080)),
+    ("honeydew", rgb(0xf0fff0)),
+    ("hotpink", rgb(0xff69b4)),
+    ("indianred", rgb(0xcd5c5c)),
+    ("indigo", rgb(0x4b0082)),
+    ("ivory", rgb(0xfffff0)),
+    ("khaki", rgb(0xf0e68c)),
+    ("lavender", rgb(0xe6e6fa)),
+    ("lavenderblush", rgb(0xfff0f5)),
+    ("lawngreen", rgb(0x7cfc00)),
+    ("lemonchiffon", rgb(0xfffacd)),
+    ("lightblue", rgb(0xadd8e6)),
+    ("lightcoral", rgb(0xf08080)),
+    ("lightcyan", rgb(0xe0ffff)),
+    ("lightgoldenrodyellow", rgb(0xfafad2)),
+    ("lightgray", rgb(0xd3d3d3)),
+    ("lightgreen", rgb(0x90ee90)),
+    ("lightgrey", rgb(0xd3d3d3)),
+    ("lightpink", rgb(0xffb6c1)),
+    ("lightsalmon", rgb(0xffa07a)),
+    ("lightseagreen", rgb(0x20b2aa)),
+    ("lightskyblue", rgb(0x87cefa)),
+    ("lightslategray", rgb(0x778899)),
+    ("lightslategrey", rgb(0x778899)),
+    ("lightsteelblue", rgb(0xb0c4de)),
+    ("lightyellow", rgb(0xffffe0)),
+    ("lime", rgb(0x00ff00)),
+    ("limegreen", rgb(0x32cd32)),
+    ("linen", rgb(0xfaf0e6)),
+    ("magenta", rgb(0xff00ff)),
+    ("maroon", rgb(0x800000)),
+    ("mediumaquamarine", rgb(0x66cdaa)),
+    ("mediumblue", rgb(0x0000cd)),
+    ("mediumorchid", rgb(0xba55d3)),
+    ("mediumpurple", rgb(0x9370db)),
+    ("mediumseagreen", rgb(0x3cb371)),
+    ("mediumslateblue", rgb(0x7b68ee)),
+    ("mediumspringgreen", rgb(0x00fa9a)),
+    ("mediumturquoise", rgb(0x48d1cc)),
+    ("mediumvioletred", rgb(0xc71585)),
+    ("midnightblue", rgb(0x191970)),
+    ("mintcream", rgb(0xf5fffa)),
+    ("mistyrose", rgb(0xffe4e1)),
+    ("moccasin", rgb(0xffe4b5)),
+    ("navajowhite", rgb(0xffdead)),
+    ("navy", rgb(0x000080)),
+    ("oldlace", rgb(0xfdf5e6)),
+    ("olive", rgb(0x808000)),
+    ("olivedrab", rgb(0x6b8e23)),
+    ("orange", rgb(0xffa500)),
+    ("orangered", rgb(0xff4500)),
+    ("orchid", rgb(0xda70d6)),
+    ("palegoldenrod", rgb(0xeee8aa)),
+    ("palegreen", rgb(0x98fb98)),
+    ("paleturquoise", rgb(0xafeeee)),
+    ("palevioletred", rgb(0xdb7093)),
+    ("papayawhip", rgb(0xffefd5)),
+    ("peachpuff", rgb(0xffdab9)),
+    ("peru", rgb(0xcd853f)),
+    ("pink", rgb(0xffc0cb)),
+    ("plum", rgb(0xdda0dd)),
+    ("powderblue", rgb(0xb0e0e6)),
+    ("purple", rgb(0x800080)),
+    ("rebeccapurple", rgb(0x663399)),
+    ("red", rgb(0xff0000)),
+    ("rosybrown", rgb(0xbc8f8f)),
+    ("royalblue", rgb(0x4169e1)),
+    ("saddlebrown", rgb(0x8b4513)),
+    ("salmon", rgb(0xfa8072)),
+    ("sandybrown", rgb(0xf4a460)),
+    ("seagreen", rgb(0x2e8b57)),
+    ("seashell", rgb(0xfff5ee)),
+    ("sienna", rgb(0xa0522d)),
+    ("silver", rgb(0xc0c0c0)),
+    ("skyblue", rgb(0x87ceeb)),
+    ("slateblue", rgb(0x6a5acd)),
+    ("slategray", rgb(0x708090)),
+    ("slategrey", rgb(0x708090)),
+    ("snow", rgb(0xfffafa)),
+    ("springgreen", rgb(0x00ff7f)),
+    ("steelblue", rgb(0x4682b4)),
+    ("tan", rgb(0xd2b48c)),
+    ("teal", rgb(0x008080)),
+    ("thistle", rgb(0xd8bfd8)),
+    ("tomato", rgb(0xff6347)),
+    ("turquoise", rgb(0x40e0d0)),
+    ("violet", rgb(0xee82ee)),
+    ("wheat", rgb(0xf5deb3)),
+    ("white", rgb(0xffffff)),
+    ("whitesmoke", rgb(0xf5f5f5)),
+    ("yellow", rgb(0xffff00)),
+    ("yellowgreen", rgb(0x9acd32)),
+];
+
+// the perceptually closest css name, exact matches give the first listed name
+pub fn nearest_name(rgb: RGB) -> &'static str {
+    CSS_COLORS
+        .iter()
+        .map(|&(name, color)| (delta_e_76(rgb, color), name))
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, name)| name)
+        .unwrap()
+}
+
+// nearest_color for repeated lookups, the palette is converted to Lab once
+// instead of on every call
+#[cfg(feature = "std")]
+pub struct LabPalette {
+    colors: Vec<(LAB, RGB)>,
+}
+
+#[cfg(feature = "std")]
+impl LabPalette {
+    pub fn new(palette: &[RGB]) -> Self {
+        let colors = palette.iter().map(|&c| (rgb_to_lab(c), c)).collect();
+        Self { colors }
+    }
+
+    pub fn nearest_index(&self, target: RGB) -> Option<usize> {
+        let target = rgb_to_lab(target);
+        self.colors
+            .iter()
+            .map(|&(lab, _)| lab_distance(target, lab))
+            .enumerate()
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    pub fn nearest(&self, target: RGB) -> Option<RGB> {
+        self.nearest_index(target).map(|i| self.colors[i].1)
+    }
+}
+
+pub fn relative_luminance(rgb: RGB) -> f32 {
+    0.2126 * srgb_to_linear(rgb.red)
+        + 0.7152 * srgb_to_linear(rgb.green)
+        + 0.0722 * srgb_to_linear(rgb.blue)
+}
+
+pub fn contrast_ratio(a: RGB, b: RGB) -> f32 {
+    let a = relative_luminance(a);
+    let b = relative_luminance(b);
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+// WCAG AA for normal text
+pub fn passes_aa(fg: RGB, bg: RGB) -> bool {
+    contrast_ratio(fg, bg) >= 4.5
+}
+
+pub fn readable_text_color(bg: RGB) -> RGB {
+    let black = rgb(0x000000);
+    let white = rgb(0xffffff);
+    if contrast_ratio(black, bg) >= contrast_ratio(white, bg) {
+        black
+    } else {
+        white
+    }
+}
+
+// the candidate with the highest contrast against bg, first one wins ties
+pub fn most_readable(bg: RGB, candidates: &[RGB]) -> Option<RGB> {
+    let mut best: Option<(RGB, f32)> = None;
+    for &candidate in candidates {
+        let ratio = contrast_ratio(candidate, bg);
+        let better = match best {
+            Some((_, r)) => ratio > r,
+            None => true,
+        };
+        if better {
+            best = Some((candidate, ratio));
+        }
+    }
+    best.map(|(candidate, _)| candidate)
+}
+
+pub fn is_dark(rgb: RGB) -> bool {
+    relative_luminance(rgb) < 0.5
+}
+
+pub fn is_light(rgb: RGB) -> bool {
+    !is_dark(rgb)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cvd {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+// color vision deficiency simulation after Viénot, Brettel and Mollon in LMS space
+pub fn simulate_cvd(rgb: RGB, kind: Cvd) -> RGB {
+    const RGB_TO_LMS: [[f64; 3]; 3] = [
+        [0.31399022, 0.63951294, 0.04649755],
+        [0.15537241, 0.75789446, 0.08670142],
+        [0.01775239, 0.10944209, 0.87256922],
+    ];
+    const LMS_TO_RGB: [[f64; 3]; 3] = [
+        [5.47221206, -4.6419601, 0.16963708],
+        [-1.1252419, 2.29317094, -0.1678952],
+        [0.02980165, -0.19318073, 1.16364789],
+    ];
+    let transform = |m: &[[f64; 3]; 3], v: [f64; 3]| {
+        let mut out = [0.0; 3];
+        for (o, row) in out.iter_mut().zip(m.iter()) {
+            *o = row[0] * v[0] + row[1] * v[1] + row[2] * v[2];
+        }
+        out
+    };
+
+    let linear = [
+        srgb_to_linear(rgb.red) as f64,
+        srgb_to_linear(rgb.green) as f64,
+        srgb_to_linear(rgb.blue) as f64,
+    ];
+    let [l, m, s] = transform(&RGB_TO_LMS, linear);
+    let lms = match kind {
+        Cvd::Protanopia => [1.05118294 * m - 0.05116099 * s, m, s],
+        Cvd::Deuteranopia => [l, 0.9513092 * l + 0.04866992 * s, s],
+        Cvd::Tritanopia => [l, m, -0.86744736 * l + 1.86727089 * m],
+    };
+    let [r, g, b] = transform(&LMS_TO_RGB, lms);
+    RGB::new(
+        linear_to_srgb(r as f32),
+        linear_to_srgb(g as f32),
+        linear_to_srgb(b as f32),
+    )
+}
+
+// 5x7 glyphs for ascii 0x20..=0x7e, one byte per column, bit 0 is the top row
+#[cfg(feature = "std")]
+const FONT_5X7: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x00, 0x00, 0x5f, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], // #
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1c, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1c, 0x00], // )
+    [0x08, 0x2a, 0x1c, 0x2a, 0x08], // *
+    [0x08, 0x08, 0x3e, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], // 0
+    [0x00, 0x42, 0x7f, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4b, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7f, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1e], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3e], // @
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], // A
+    [0x7f, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3e, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], // D
+    [0x7f, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7f, 0x09, 0x09, 0x01, 0x01], // F
+    [0x3e, 0x41, 0x41, 0x51, 0x32], // G
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], // H
+    [0x00, 0x41, 0x7f, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], // J
+    [0x7f, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7f, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7f, 0x02, 0x04, 0x02, 0x7f], // M
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], // N
+    [0x3e, 0x41, 0x41, 0x41, 0x3e], // O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3e, 0x41, 0x51, 0x21, 0x5e], // Q
+    [0x7f, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7f, 0x01, 0x01], // T
+    [0x3f, 0x40, 0x40, 0x40, 0x3f], // U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], // V
+    [0x7f, 0x20, 0x18, 0x20, 0x7f], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x03, 0x04, 0x78, 0x04, 0x03], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x00, 0x7f, 0x41, 0x41], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x41, 0x41, 0x7f, 0x00, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7f], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7e, 0x09, 0x01, 0x02], // f
+    [0x08, 0x14, 0x54, 0x54, 0x3c], // g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7d, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3d, 0x00], // j
+    [0x00, 0x7f, 0x10, 0x28, 0x44], // k
+    [0x00, 0x41, 0x7f, 0x40, 0x00], // l
+    [0x7c, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7c, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7c], // q
+    [0x7c, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], // t
+    [0x3c, 0x40, 0x40, 0x20, 0x7c], // u
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], // v
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0c, 0x50, 0x50, 0x50, 0x3c], // y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7f, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x10, 0x08, 0x08, 0x10, 0x08], // ~
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+}
+
+#[cfg(feature = "std")]
+pub struct Pixels {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl Pixels {
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width * height * 3) as usize;
+        let data = vec![255; size];
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn checkerboard(width: u32, height: u32, tile: u32, a: RGB, b: RGB) -> Self {
+        let tile = tile.max(1);
+        let mut pixels = Self::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let color = if (x / tile + y / tile) & 1 == 0 { a } else { b };
+                pixels.set(x, y, color);
+            }
+        }
+        pixels
+    }
+
+    pub fn save_image(&self, name: impl AsRef<Path>) -> Result<(), ColorError> {
+        let file = File::create(name)?;
+        let w = BufWriter::new(file);
+
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+
+        writer.write_image_data(&self.data)?;
+        Ok(())
+    }
+
+    // one luminance byte per pixel, color images are collapsed to their luminance
+    pub fn save_image_gray(&self, name: impl AsRef<Path>) -> Result<(), ColorError> {
+        let file = File::create(name)?;
+        let w = BufWriter::new(file);
+
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+
+        let data: Vec<u8> = self
+            .data
+            .chunks_exact(3)
+            .map(|c| luminance(RGB::new(c[0], c[1], c[2])))
+            .collect();
+        writer.write_image_data(&data)?;
+        Ok(())
+    }
+
+    pub fn save_ppm(&self, name: impl AsRef<Path>) -> Result<(), ColorError> {
+        let file = File::create(name)?;
+        let mut w = BufWriter::new(file);
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        w.write_all(&self.data)?;
+        w.flush()?;
+        Ok(())
+    }
+
+    // 24-bit BITMAPINFOHEADER, rows are stored bottom-up as BGR padded to 4 bytes
+    pub fn save_bmp(&self, name: impl AsRef<Path>) -> Result<(), ColorError> {
+        let stride = self.width * 3;
+        let padding = (4 - stride % 4) % 4;
+        let image_size = (stride + padding) * self.height;
+        let offset = 14 + 40;
+
+        let file = File::create(name)?;
+        let mut w = BufWriter::new(file);
+        w.write_all(b"BM")?;
+        w.write_all(&(offset + image_size).to_le_bytes())?;
+        w.write_all(&0u32.to_le_bytes())?;
+        w.write_all(&offset.to_le_bytes())?;
+
+        w.write_all(&40u32.to_le_bytes())?;
+        w.write_all(&(self.width as i32).to_le_bytes())?;
+        w.write_all(&(self.height as i32).to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?;
+        w.write_all(&24u16.to_le_bytes())?;
+        w.write_all(&0u32.to_le_bytes())?;
+        w.write_all(&image_size.to_le_bytes())?;
+        w.write_all(&2835i32.to_le_bytes())?;
+        w.write_all(&2835i32.to_le_bytes())?;
+        w.write_all(&0u32.to_le_bytes())?;
+        w.write_all(&0u32.to_le_bytes())?;
+
+        let mut row = Vec::with_capacity((stride + padding) as usize);
+        for y in (0..self.height).rev() {
+            row.clear();
+            let start = (y * stride) as usize;
+            for c in self.data[start..start + stride as usize].chunks_exact(3) {
+                row.extend_from_slice(&[c[2], c[1], c[0]]);
+            }
+            row.resize((stride + padding) as usize, 0);
+            w.write_all(&row)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
+    // w x h pixels from (x, y), clipped at the canvas bounds
+    pub fn rect(&mut self, x: u32, y: u32, w: u32, h: u32, rgb: RGB) {
+        let x1 = x.saturating_add(w).min(self.width);
+        let y1 = y.saturating_add(h).min(self.height);
+        for y in y..y1 {
+            for x in x..x1 {
+                self.set(x, y, rgb);
+            }
+        }
+    }
+
+    // set that ignores points outside the canvas
+    fn plot(&mut self, x: i32, y: i32, rgb: RGB) {
+        if x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height {
+            self.set(x as u32, y as u32, rgb);
+        }
+    }
+
+    pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, rgb: RGB) {
+        bresenham(x0, y0, x1, y1, |x, y| self.plot(x, y, rgb));
+    }
+
+    // stamps a square brush of the given width along the line, width 0 draws nothing
+    pub fn line_thick(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, width: u32, rgb: RGB) {
+        if width == 0 {
+            return;
+        }
+        let lo = -((width as i32 - 1) / 2);
+        let hi = width as i32 / 2;
+        bresenham(x0, y0, x1, y1, |x, y| {
+            for dy in lo..=hi {
+                for dx in lo..=hi {
+                    self.plot(x + dx, y + dy, rgb);
+                }
+            }
+        });
+    }
+
+    // horizontal run from x0 to x1 inclusive, clipped at the canvas bounds
+    fn span(&mut self, x0: i32, x1: i32, y: i32, rgb: RGB) {
+        if y < 0 || y >= self.height as i32 {
+            return;
+        }
+        for x in x0.max(0)..=x1.min(self.width as i32 - 1) {
+            self.set(x as u32, y as u32, rgb);
+        }
+    }
+
+    pub fn ellipse(&mut self, cx: i32, cy: i32, rx: u32, ry: u32, rgb: RGB) {
+        midpoint_ellipse(rx, ry, |x, y| {
+            self.plot(cx + x, cy + y, rgb);
+            self.plot(cx - x, cy + y, rgb);
+            self.plot(cx + x, cy - y, rgb);
+            self.plot(cx - x, cy - y, rgb);
+        });
+    }
+
+    pub fn circle(&mut self, cx: i32, cy: i32, radius: u32, rgb: RGB) {
+        self.ellipse(cx, cy, radius, radius, rgb);
+    }
+
+    // degrees run counterclockwise from the positive x axis, end < start wraps through 0
+    pub fn arc(&mut self, cx: i32, cy: i32, radius: u32, start_deg: u32, end_deg: u32, rgb: RGB) {
+        let full = end_deg.saturating_sub(start_deg) >= 360;
+        let (start, end) = ((start_deg % 360) as f32, (end_deg % 360) as f32);
+        let inside = |angle: f32| {
+            full || if start <= end {
+                start <= angle && angle <= end
+            } else {
+                angle >= start || angle <= end
+            }
+        };
+        midpoint_ellipse(radius, radius, |x, y| {
+            for &(dx, dy) in [(x, y), (-x, y), (x, -y), (-x, -y)].iter() {
+                let angle = (dy as f32).atan2(dx as f32).to_degrees().rem_euclid(360.0);
+                if inside(angle) {
+                    self.plot(cx + dx, cy - dy, rgb);
+                }
+            }
+        });
+    }
+
+    pub fn filled_ellipse(&mut self, cx: i32, cy: i32, rx: u32, ry: u32, rgb: RGB) {
+        midpoint_ellipse(rx, ry, |x, y| {
+            self.span(cx - x, cx + x, cy + y, rgb);
+            self.span(cx - x, cx + x, cy - y, rgb);
+        });
+    }
+
+    // a single point is plotted on its own
+    pub fn polyline(&mut self, points: &[(i32, i32)], closed: bool, rgb: RGB) {
+        if let [(x, y)] = points {
+            self.plot(*x, *y, rgb);
+        }
+        for pair in points.windows(2) {
+            self.line(pair[0].0, pair[0].1, pair[1].0, pair[1].1, rgb);
+        }
+        if closed && points.len() > 2 {
+            let (first, last) = (points[0], points[points.len() - 1]);
+            self.line(last.0, last.1, first.0, first.1, rgb);
+        }
+    }
+
+    pub fn rect_outline(&mut self, x: u32, y: u32, w: u32, h: u32, rgb: RGB) {
+        if w == 0 || h == 0 || x >= self.width || y >= self.height {
+            return;
+        }
+        // far edges past the canvas stop one pixel outside it so they get clipped
+        let far = |start: u32, len: u32, size: u32| {
+            (start as i64 + len as i64 - 1).min(size as i64) as i32
+        };
+        let (x0, y0) = (x as i32, y as i32);
+        let (x1, y1) = (far(x, w, self.width), far(y, h, self.height));
+        self.line(x0, y0, x1, y0, rgb);
+        self.line(x0, y1, x1, y1, rgb);
+        self.line(x0, y0, x0, y1, rgb);
+        self.line(x1, y0, x1, y1, rgb);
+    }
+
+    pub fn grid(&mut self, spacing: u32, rgb: RGB) {
+        self.grid_offset(spacing, 0, 0, rgb);
+    }
+
+    // gridlines every spacing pixels, shifted so one passes through (dx, dy)
+    pub fn grid_offset(&mut self, spacing: u32, dx: u32, dy: u32, rgb: RGB) {
+        if spacing == 0 || self.width == 0 || self.height == 0 {
+            return;
+        }
+        let (right, bottom) = (self.width as i32 - 1, self.height as i32 - 1);
+        for x in (dx % spacing..self.width).step_by(spacing as usize) {
+            self.line(x as i32, 0, x as i32, bottom, rgb);
+        }
+        for y in (dy % spacing..self.height).step_by(spacing as usize) {
+            self.line(0, y as i32, right, y as i32, rgb);
+        }
+    }
+
+    // even-odd rule, sampled at pixel centers
+    pub fn fill_polygon(&mut self, points: &[(i32, i32)], rgb: RGB) {
+        if points.len() < 3 {
+            return;
+        }
+        let top = points.iter().map(|p| p.1).min().unwrap().max(0);
+        let bottom = points
+            .iter()
+            .map(|p| p.1)
+            .max()
+            .unwrap()
+            .min(self.height as i32);
+        let mut crossings = Vec::new();
+        for y in top..bottom {
+            let yc = y as f32 + 0.5;
+            crossings.clear();
+            for (i, &(xi, yi)) in points.iter().enumerate() {
+                let (xj, yj) = points[(i + 1) % points.len()];
+                if (yi as f32 <= yc) != (yj as f32 <= yc) {
+                    let t = (yc - yi as f32) / (yj - yi) as f32;
+                    crossings.push(xi as f32 + t * (xj - xi) as f32);
+                }
+            }
+            crossings.sort_by(|a, b| a.total_cmp(b));
+            for span in crossings.chunks_exact(2) {
+                let start = (span[0] - 0.5).ceil().max(0.0) as u32;
+                let end = ((span[1] - 0.5).ceil().max(0.0) as u32).min(self.width);
+                for x in start..end {
+                    self.set(x, y as u32, rgb);
+                }
+            }
+        }
+    }
+
+    // glyphs advance 6 pixels, unsupported characters draw a box
+    pub fn draw_text(&mut self, x: u32, y: u32, text: &str, rgb: RGB) {
+        if y >= self.height {
+            return;
+        }
+        for (i, c) in text.chars().enumerate() {
+            // the rest of the string starts past the right edge too
+            let left = x as u64 + i as u64 * 6;
+            if left >= self.width as u64 {
+                break;
+            }
+            let left = left as u32;
+            let glyph = match c {
+                ' '..='~' => FONT_5X7[c as usize - 0x20],
+                _ => {
+                    self.rect_outline(left, y, 5, 7, rgb);
+                    continue;
+                }
+            };
+            for (dx, column) in glyph.iter().enumerate() {
+                for dy in 0..7 {
+                    if column & (1 << dy) != 0 {
+                        self.plot((left + dx as u32) as i32, (y + dy) as i32, rgb);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn simulate_cvd(&mut self, kind: Cvd) {
+        for c in self.data.chunks_exact_mut(3) {
+            let rgb: [u8; 3] = simulate_cvd(RGB::new(c[0], c[1], c[2]), kind).into();
+            c.copy_from_slice(&rgb);
+        }
+    }
+
+    pub fn sepia(&mut self) {
+        for c in self.data.chunks_exact_mut(3) {
+            let rgb: [u8; 3] = sepia(RGB::new(c[0], c[1], c[2])).into();
+            c.copy_from_slice(&rgb);
+        }
+    }
+
+    // snaps every channel to the nearest of `levels` evenly spaced steps, fewer than 2 is a no-op
+    pub fn posterize(&mut self, levels: u8) {
+        if levels < 2 {
+            return;
+        }
+        let n = levels as u32 - 1;
+        for c in self.data.iter_mut() {
+            let step = (*c as u32 * n + 127) / 255;
+            *c = (step * 255 / n) as u8;
+        }
+    }
+
+    pub fn adjust_brightness(&mut self, delta: i32) {
+        for c in self.data.iter_mut() {
+            *c = (*c as i32).saturating_add(delta).clamp(0, 255) as u8;
+        }
+    }
+
+    // scales every channel around the 128 midpoint
+    pub fn adjust_contrast(&mut self, factor: f32) {
+        for c in self.data.iter_mut() {
+            *c = f32_to_u8_clamped((*c as f32 - 128.0) * factor + 128.0);
+        }
+    }
+
+    pub fn rotate_hue(&mut self, degrees_tenths: i32) {
+        for c in self.data.chunks_exact_mut(3) {
+            let rgb: [u8; 3] = rotate_hue(RGB::new(c[0], c[1], c[2]), degrees_tenths).into();
+            c.copy_from_slice(&rgb);
+        }
+    }
+
+    // raises every normalized channel to 1/gamma through a lookup table
+    pub fn apply_gamma(&mut self, gamma: f32) {
+        if gamma <= 0.0 {
+            return;
+        }
+        let mut table = [0u8; 256];
+        for (i, v) in table.iter_mut().enumerate() {
+            *v = f32_to_u8_clamped((i as f32 / 255.0).powf(1.0 / gamma) * 255.0);
+        }
+        for_each_row(&mut self.data, self.width, |_, row| {
+            row.iter_mut().for_each(|c| *c = table[*c as usize])
+        });
+    }
+
+    pub fn invert(&mut self) {
+        for_each_row(&mut self.data, self.width, |_, row| {
+            row.iter_mut().for_each(|c| *c = 255 - *c)
+        });
+    }
+
+    pub fn tint(&mut self, color: RGB, strength: u16) {
+        for c in self.data.chunks_exact_mut(3) {
+            let rgb: [u8; 3] = mix(strength, RGB::new(c[0], c[1], c[2]), color).into();
+            c.copy_from_slice(&rgb);
+        }
+    }
+
+    // Floyd-Steinberg error diffusion
+    pub fn dither_to_palette(&mut self, palette: &[RGB]) {
+        if palette.is_empty() {
+            return;
+        }
+        let palette = LabPalette::new(palette);
+        let width = self.width as i32;
+        let height = self.height as i32;
+        let mut values: Vec<i32> = self.data.iter().map(|&c| c as i32).collect();
+        for y in 0..height {
+            for x in 0..width {
+                let index = ((y * width + x) * 3) as usize;
+                let mut old = RGB::default();
+                for c in 0..=2 {
+                    old[c] = values[index + c].clamp(0, 255) as u8;
+                }
+                let new = palette.nearest(old).unwrap();
+                self.data[index..index + 3].copy_from_slice(&<[u8; 3]>::from(new));
+                for c in 0..=2 {
+                    let error = old[c] as i32 - new[c] as i32;
+                    for &(dx, dy, weight) in &[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)] {
+                        let (nx, ny) = (x + dx, y + dy);
+                        if nx >= 0 && nx < width && ny < height {
+                            let neighbor = ((ny * width + nx) * 3) as usize + c;
+                            values[neighbor] += error * weight / 16;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn grayscale(&mut self, coeffs: LumaCoeffs) {
+        for_each_row(&mut self.data, self.width, |_, row| {
+            for c in row.chunks_exact_mut(3) {
+                let gray: [u8; 3] = to_grayscale(RGB::new(c[0], c[1], c[2]), coeffs).into();
+                c.copy_from_slice(&gray);
+            }
+        });
+    }
+
+    // one channel as a grayscale image
+    pub fn channel(&self, ch: Channel) -> Pixels {
+        let i = ch as usize;
+        let data = self.data.chunks_exact(3).flat_map(|c| [c[i]; 3]).collect();
+        Pixels {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+
+    // takes red from r, green from g and blue from b
+    pub fn from_channels(r: &Pixels, g: &Pixels, b: &Pixels) -> Result<Pixels, ColorError> {
+        for other in [g, b] {
+            if (other.width, other.height) != (r.width, r.height) {
+                return Err(ColorError::SizeMismatch {
+                    expected: (r.width, r.height),
+                    found: (other.width, other.height),
+                });
+            }
+        }
+        let data = r
+            .data
+            .chunks_exact(3)
+            .zip(g.data.chunks_exact(3))
+            .zip(b.data.chunks_exact(3))
+            .flat_map(|((r, g), b)| [r[0], g[1], b[2]])
+            .collect();
+        Ok(Pixels {
+            width: r.width,
+            height: r.height,
+            data,
+        })
+    }
+
+    // moves the red and blue channels by their offsets, green stays in place
+    pub fn rgb_shift(&mut self, dx_r: i32, dy_r: i32, dx_b: i32, dy_b: i32) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let source = self.data.clone();
+        let width = self.width;
+        let (right, bottom) = (width as i32 - 1, self.height as i32 - 1);
+        let sample = |x: i32, y: i32, c: usize| {
+            let x = x.clamp(0, right) as u32;
+            let y = y.clamp(0, bottom) as u32;
+            source[((y * width + x) * 3) as usize + c]
+        };
+        for y in 0..self.height as i32 {
+            for x in 0..self.width as i32 {
+                let index = ((y as u32 * self.width + x as u32) * 3) as usize;
+                self.data[index] = sample(x.saturating_sub(dx_r), y.saturating_sub(dy_r), 0);
+                self.data[index + 2] = sample(x.saturating_sub(dx_b), y.saturating_sub(dy_b), 2);
+            }
+        }
+    }
+
+    // darkens towards the corners, by 1 - strength at the very corner
+    pub fn vignette(&mut self, strength: f32) {
+        let strength = strength.clamp(0.0, 1.0);
+        if strength == 0.0 {
+            return;
+        }
+        let (cx, cy) = (self.width as f32 / 2.0, self.height as f32 / 2.0);
+        let corner = cx * cx + cy * cy;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+                let falloff = 1.0 - strength * (dx * dx + dy * dy) / corner;
+                let mut rgb = self.get(x, y);
+                for i in 0..=2 {
+                    rgb[i] = f32_to_u8_clamped(rgb[i] as f32 * falloff);
+                }
+                self.set(x, y, rgb);
+            }
+        }
+    }
+
+    // 0 is grayscale, 1 leaves the image alone and above 1 boosts the colors
+    pub fn adjust_saturation(&mut self, factor: f32) {
+        let factor = factor.max(0.0);
+        for_each_row(&mut self.data, self.width, |_, row| {
+            for c in row.chunks_exact_mut(3) {
+                let gray = luminance(RGB::new(c[0], c[1], c[2])) as f32;
+                for v in c.iter_mut() {
+                    *v = f32_to_u8_clamped(gray + (*v as f32 - gray) * factor);
+                }
+            }
+        });
+    }
+
+    pub fn threshold(&mut self, level: u8) {
+        for c in self.data.chunks_exact_mut(3) {
+            let value = if luminance(RGB::new(c[0], c[1], c[2])) >= level {
+                255
+            } else {
+                0
+            };
+            c.copy_from_slice(&[value; 3]);
+        }
+    }
+
+    // averages the (2r+1)x(2r+1) window, clipped at the edges
+    pub fn box_blur(&mut self, radius: u32) {
+        if radius == 0 {
+            return;
+        }
+        let radius = radius.min(self.width.max(self.height));
+        let source = self.data.clone();
+        for y in 0..self.height {
+            let y0 = y.saturating_sub(radius);
+            let y1 = (y + radius).min(self.height - 1);
+            for x in 0..self.width {
+                let x0 = x.saturating_sub(radius);
+                let x1 = (x + radius).min(self.width - 1);
+                let mut sum = [0u32; 3];
+                for sy in y0..=y1 {
+                    for sx in x0..=x1 {
+                        let index = ((sy * self.width + sx) * 3) as usize;
+                        for c in 0..=2 {
+                            sum[c] += source[index + c] as u32;
+                        }
+                    }
+                }
+                let count = (x1 - x0 + 1) * (y1 - y0 + 1);
+                self.set(x, y, sum.map(|v| (v / count) as u8).into());
+            }
+        }
+    }
+
+    // per channel median of the window, windows shrink at the edges like box_blur
+    pub fn median_filter(&mut self, radius: u32) {
+        if radius == 0 {
+            return;
+        }
+        let radius = radius.min(self.width.max(self.height));
+        let source = self.data.clone();
+        let mut window: [Vec<u8>; 3] = Default::default();
+        for y in 0..self.height {
+            let y0 = y.saturating_sub(radius);
+            let y1 = (y + radius).min(self.height - 1);
+            for x in 0..self.width {
+                let x0 = x.saturating_sub(radius);
+                let x1 = (x + radius).min(self.width - 1);
+                window.iter_mut().for_each(|w| w.clear());
+                for sy in y0..=y1 {
+                    for sx in x0..=x1 {
+                        let index = ((sy * self.width + sx) * 3) as usize;
+                        for (c, w) in window.iter_mut().enumerate() {
+                            w.push(source[index + c]);
+                        }
+                    }
+                }
+                let mut out = RGB::default();
+                for (c, w) in window.iter_mut().enumerate() {
+                    w.sort_unstable();
+                    out[c] = w[w.len() / 2];
+                }
+                self.set(x, y, out);
+            }
+        }
+    }
+
+    // smooth value noise with features about scale pixels across, mapped through ramp
+    pub fn value_noise(&mut self, seed: u64, scale: f32, ramp: &[RGB]) {
+        if ramp.is_empty() {
+            return;
+        }
+        let scale = if scale > 0.0 { scale } else { 1.0 };
+        let last = ramp.len() - 1;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let t = value_noise_2d(seed, x as f32 / scale, y as f32 / scale) * last as f32;
+                let i = (t as usize).min(last);
+                let rgb = match ramp.get(i + 1) {
+                    Some(&next) => mix_f(t - i as f32, ramp[i], next),
+                    None => ramp[i],
+                };
+                self.set(x, y, rgb);
+            }
+        }
+    }
+
+    // 3x3 kernel applied over a copy of the buffer, samples are clamped at the edges
+    pub fn convolve(&mut self, kernel: &[[f32; 3]; 3], divisor: f32) {
+        let source = Pixels {
+            width: self.width,
+            height: self.height,
+            data: self.data.clone(),
+        };
+        for_each_row(&mut self.data, self.width, |y, out| {
+            source.convolve_row(kernel, divisor, y as i32, out)
+        });
+    }
+
+    fn convolve_row(&self, kernel: &[[f32; 3]; 3], divisor: f32, y: i32, out: &mut [u8]) {
+        let width = self.width as i32;
+        let height = self.height as i32;
+        for (x, out) in out.chunks_exact_mut(3).enumerate() {
+            let mut sum = [0.0f32; 3];
+            for (ky, row) in kernel.iter().enumerate() {
+                let sy = (y + ky as i32 - 1).clamp(0, height - 1);
+                for (kx, weight) in row.iter().enumerate() {
+                    let sx = (x as i32 + kx as i32 - 1).clamp(0, width - 1);
+                    let index = ((sy * width + sx) * 3) as usize;
+                    for (sum, &c) in sum.iter_mut().zip(&self.data[index..index + 3]) {
+                        *sum += c as f32 * weight;
+                    }
+                }
+            }
+            for (out, sum) in out.iter_mut().zip(sum.iter()) {
+                *out = f32_to_u8_clamped(sum / divisor);
+            }
+        }
+    }
+
+    pub fn sharpen(&mut self, amount: f32) {
+        if amount == 0.0 {
+            return;
+        }
+        let a = amount;
+        self.convolve(
+            &[[0.0, -a, 0.0], [-a, 1.0 + 4.0 * a, -a], [0.0, -a, 0.0]],
+            1.0,
+        );
+    }
+
+    // gradient magnitude of the luminance as a new grayscale image
+    pub fn sobel(&self) -> Pixels {
+        let luma: Vec<i32> = self
+            .data
+            .chunks_exact(3)
+            .map(|c| luminance(RGB::new(c[0], c[1], c[2])) as i32)
+            .collect();
+        let width = self.width as i32;
+        let height = self.height as i32;
+        let at = |x: i32, y: i32| {
+            let x = x.clamp(0, width - 1);
+            let y = y.clamp(0, height - 1);
+            luma[(y * width + x) as usize]
+        };
+        let mut out = Pixels::new(self.width, self.height);
+        for y in 0..height {
+            for x in 0..width {
+                let gx = at(x + 1, y - 1) + 2 * at(x + 1, y) + at(x + 1, y + 1)
+                    - at(x - 1, y - 1)
+                    - 2 * at(x - 1, y)
+                    - at(x - 1, y + 1);
+                let gy = at(x - 1, y + 1) + 2 * at(x, y + 1) + at(x + 1, y + 1)
+                    - at(x - 1, y - 1)
+                    - 2 * at(x, y - 1)
+                    - at(x + 1, y - 1);
+                let magnitude = ((gx * gx + gy * gy) as f32).sqrt();
+                out.set(x as u32, y as u32, [f32_to_u8_clamped(magnitude); 3].into());
+            }
+        }
+        out
+    }
+
+    // fills every block x block tile with its average, partial tiles at the edges included
+    pub fn pixelate(&mut self, block: u32) {
+        if block == 0 {
+            return;
+        }
+        for ty in (0..self.height).step_by(block as usize) {
+            let y1 = ty.saturating_add(block).min(self.height);
+            for tx in (0..self.width).step_by(block as usize) {
+                let x1 = tx.saturating_add(block).min(self.width);
+                let mut colors = Vec::with_capacity(((x1 - tx) * (y1 - ty)) as usize);
+                for y in ty..y1 {
+                    for x in tx..x1 {
+                        colors.push(self.get(x, y));
+                    }
+                }
+                let color = average(&colors);
+                for y in ty..y1 {
+                    for x in tx..x1 {
+                        self.set(x, y, color);
+                    }
+                }
+            }
+        }
+    }
+
+    // 4-connected fill with an explicit stack so large regions can't overflow
+    pub fn flood_fill(&mut self, x: u32, y: u32, fill: RGB) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let target = self.get(x, y);
+        if target == fill {
+            return;
+        }
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            if self.get(x, y) != target {
+                continue;
+            }
+            self.set(x, y, fill);
+            if x > 0 {
+                stack.push((x - 1, y));
+            }
+            if x + 1 < self.width {
+                stack.push((x + 1, y));
+            }
+            if y > 0 {
+                stack.push((x, y - 1));
+            }
+            if y + 1 < self.height {
+                stack.push((x, y + 1));
+            }
+        }
+    }
+
+    pub fn flip_h(&mut self) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let stride = (self.width * 3) as usize;
+        for row in self.data.chunks_exact_mut(stride) {
+            row.reverse();
+            row.chunks_exact_mut(3).for_each(|c| c.reverse());
+        }
+    }
+
+    pub fn flip_v(&mut self) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let stride = (self.width * 3) as usize;
+        let height = self.height as usize;
+        for y in 0..height / 2 {
+            let (top, bottom) = self.data.split_at_mut((height - 1 - y) * stride);
+            top[y * stride..(y + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+        }
+    }
+
+    pub fn resize_nearest(&self, new_w: u32, new_h: u32) -> Pixels {
+        let mut out = Pixels::new(new_w, new_h);
+        if new_w == 0 || new_h == 0 || self.width == 0 || self.height == 0 {
+            return out;
+        }
+        for y in 0..new_h {
+            let sy = (y as u64 * self.height as u64 / new_h as u64) as u32;
+            for x in 0..new_w {
+                let sx = (x as u64 * self.width as u64 / new_w as u64) as u32;
+                out.set(x, y, self.get(sx, sy));
+            }
+        }
+        out
+    }
+
+    pub fn resize_bilinear(&self, new_w: u32, new_h: u32) -> Pixels {
+        let mut out = Pixels::new(new_w, new_h);
+        if new_w == 0 || new_h == 0 || self.width == 0 || self.height == 0 {
+            return out;
+        }
+        // maps pixel centers and returns the two neighbors with the fraction between them
+        let sample = |d: u32, dst: u32, src: u32| {
+            let s = ((d as f32 + 0.5) * src as f32 / dst as f32 - 0.5).clamp(0.0, (src - 1) as f32);
+            let s0 = s.floor() as u32;
+            (s0, (s0 + 1).min(src - 1), s - s0 as f32)
+        };
+        for y in 0..new_h {
+            let (y0, y1, fy) = sample(y, new_h, self.height);
+            for x in 0..new_w {
+                let (x0, x1, fx) = sample(x, new_w, self.width);
+                let (a, b) = (self.get(x0, y0), self.get(x1, y0));
+                let (c, d) = (self.get(x0, y1), self.get(x1, y1));
+                let mut rgb = RGB::default();
+                for i in 0..=2 {
+                    let top = a[i] as f32 + (b[i] as f32 - a[i] as f32) * fx;
+                    let bottom = c[i] as f32 + (d[i] as f32 - c[i] as f32) * fx;
+                    rgb[i] = f32_to_u8_clamped(top + (bottom - top) * fy);
+                }
+                out.set(x, y, rgb);
+            }
+        }
+        out
+    }
+
+    // averages the source pixels under each destination pixel in linear light
+    pub fn resize_box_linear(&self, new_w: u32, new_h: u32) -> Pixels {
+        let mut out = Pixels::new(new_w, new_h);
+        if new_w == 0 || new_h == 0 || self.width == 0 || self.height == 0 {
+            return out;
+        }
+        let mut linear = [0.0f32; 256];
+        for (c, l) in linear.iter_mut().enumerate() {
+            *l = srgb_to_linear(c as u8);
+        }
+        // source span covered by destination pixel d, at least one pixel wide
+        let span = |d: u32, dst: u32, src: u32| {
+            let s0 = (d as u64 * src as u64 / dst as u64) as u32;
+            let s1 = ((d as u64 + 1) * src as u64).div_ceil(dst as u64) as u32;
+            (s0, s1.max(s0 + 1))
+        };
+        for y in 0..new_h {
+            let (y0, y1) = span(y, new_h, self.height);
+            for x in 0..new_w {
+                let (x0, x1) = span(x, new_w, self.width);
+                let mut sum = [0.0f32; 3];
+                for sy in y0..y1 {
+                    for sx in x0..x1 {
+                        let c = self.get(sx, sy);
+                        for i in 0..=2 {
+                            sum[i] += linear[c[i] as usize];
+                        }
+                    }
+                }
+                let count = ((x1 - x0) * (y1 - y0)) as f32;
+                let mut rgb = RGB::default();
+                for i in 0..=2 {
+                    rgb[i] = linear_to_srgb(sum[i] / count);
+                }
+                out.set(x, y, rgb);
+            }
+        }
+        out
+    }
+
+    // the region is clamped to the source bounds
+    pub fn crop(&self, x: u32, y: u32, w: u32, h: u32) -> Pixels {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        let w = w.min(self.width - x);
+        let h = h.min(self.height - y);
+        let mut out = Pixels::new(w, h);
+        let stride = (w * 3) as usize;
+        for row in 0..h {
+            let start = (((y + row) * self.width + x) * 3) as usize;
+            let dest = (row * w * 3) as usize;
+            out.data[dest..dest + stride].copy_from_slice(&self.data[start..start + stride]);
+        }
+        out
+    }
+
+    // opaque copy, anything outside of the destination is clipped
+    pub fn blit(&mut self, src: &Pixels, dest_x: u32, dest_y: u32) {
+        if dest_x >= self.width || dest_y >= self.height {
+            return;
+        }
+        let w = src.width.min(self.width - dest_x);
+        let h = src.height.min(self.height - dest_y);
+        let stride = (w * 3) as usize;
+        for row in 0..h {
+            let start = (row * src.width * 3) as usize;
+            let dest = (((dest_y + row) * self.width + dest_x) * 3) as usize;
+            self.data[dest..dest + stride].copy_from_slice(&src.data[start..start + stride]);
+        }
+    }
+
+    pub fn histogram(&self) -> HashMap<RGB, u32> {
+        let mut counts = HashMap::new();
+        for c in self.data.chunks_exact(3) {
+            *counts.entry(RGB::new(c[0], c[1], c[2])).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    // most frequent color, ties go to the lowest 0xRRGGBB value
+    pub fn dominant_color(&self) -> Option<RGB> {
+        self.histogram()
+            .into_iter()
+            .max_by(|a, b| {
+                let (ca, cb): ([u8; 3], [u8; 3]) = (a.0.into(), b.0.into());
+                a.1.cmp(&b.1).then(cb.cmp(&ca))
+            })
+            .map(|(color, _)| color)
+    }
+
+    // (x, y, color) in row-major order
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32, RGB)> + '_ {
+        let width = self.width;
+        self.data.chunks_exact(3).enumerate().map(move |(i, c)| {
+            let i = i as u32;
+            (i % width, i / width, RGB::new(c[0], c[1], c[2]))
+        })
+    }
+
+    pub fn pixels_mut(&mut self) -> impl Iterator<Item = (u32, u32, &mut [u8; 3])> + '_ {
+        let width = self.width;
+        self.data
+            .chunks_exact_mut(3)
+            .enumerate()
+            .map(move |(i, c)| {
+                let i = i as u32;
+                (i % width, i / width, <&mut [u8; 3]>::try_from(c).unwrap())
+            })
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> RGB {
+        let index = ((y * self.width + x) * 3) as usize;
+        RGB::new(self.data[index], self.data[index + 1], self.data[index + 2])
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, rgb: RGB) {
+        let index = ((y * self.width + x) * 3) as usize;
+        self.data[index] = rgb.red;
+        self.data[index + 1] = rgb.green;
+        self.data[index + 2] = rgb.blue;
+    }
+
+    pub fn to_rgba(&self, alpha: u8) -> PixelsRgba {
+        let mut data = Vec::with_capacity(self.data.len() / 3 * 4);
+        for c in self.data.chunks_exact(3) {
+            data.extend_from_slice(&[c[0], c[1], c[2], alpha]);
+        }
+        PixelsRgba {
+            width: self.width,
+            height: self.height,
+            data,
+            premultiplied: false,
+        }
+    }
+
+    // widens every channel so 0xff maps to 0xffff
+    pub fn to_16(&self) -> Pixels16 {
+        Pixels16 {
+            width: self.width,
+            height: self.height,
+            data: self.data.iter().map(|&c| c as u16 * 257).collect(),
+        }
+    }
+}
+
+// writes an rgb png one scanline at a time, without holding the whole image
+#[cfg(feature = "std")]
+pub struct PngRowWriter {
+    stream: png::StreamWriter<'static, BufWriter<File>>,
+    row_len: usize,
+    height: u32,
+    rows_left: u32,
+}
+
+#[cfg(feature = "std")]
+impl PngRowWriter {
+    pub fn create(name: impl AsRef<Path>, width: u32, height: u32) -> Result<Self, ColorError> {
+        let file = File::create(name)?;
+        let w = BufWriter::new(file);
+
+        let mut encoder = png::Encoder::new(w, width, height);
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Eight);
+        let writer = encoder.write_header()?;
+
+        Ok(Self {
+            stream: writer.into_stream_writer(),
+            row_len: width as usize * 3,
+            height,
+            rows_left: height,
+        })
+    }
+
+    pub fn write_row(&mut self, row: &[u8]) -> Result<(), ColorError> {
+        if row.len() != self.row_len {
+            return Err(ColorError::RowLength {
+                expected: self.row_len,
+                found: row.len(),
+            });
+        }
+        if self.rows_left == 0 {
+            return Err(ColorError::RowCount {
+                expected: self.height,
+                found: self.height.saturating_add(1),
+            });
+        }
+        self.stream.write_all(row)?;
+        self.rows_left -= 1;
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), ColorError> {
+        if self.rows_left > 0 {
+            return Err(ColorError::RowCount {
+                expected: self.height,
+                found: self.height - self.rows_left,
+            });
+        }
+        self.stream.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+pub struct PixelsRgba {
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+    premultiplied: bool,
+}
+
+#[cfg(feature = "std")]
+impl PixelsRgba {
+    // opaque white like Pixels::new, stored with straight alpha
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width * height * 4) as usize;
+        let data = vec![255; size];
+        Self {
+            width,
+            height,
+            data,
+            premultiplied: false,
+        }
+    }
+
+    pub fn is_premultiplied(&self) -> bool {
+        self.premultiplied
+    }
+
+    pub fn premultiply(&mut self) {
+        if !self.premultiplied {
+            self.data = self.premultiplied_data();
+            self.premultiplied = true;
+        }
+    }
+
+    pub fn unpremultiply(&mut self) {
+        if self.premultiplied {
+            self.data = self.straight_data();
+            self.premultiplied = false;
+        }
+    }
+
+    fn premultiplied_data(&self) -> Vec<u8> {
+        if self.premultiplied {
+            return self.data.clone();
+        }
+        let mut data = self.data.clone();
+        for c in data.chunks_exact_mut(4) {
+            c.copy_from_slice(&premultiply([c[0], c[1], c[2], c[3]]));
+        }
+        data
+    }
+
+    fn straight_data(&self) -> Vec<u8> {
+        if !self.premultiplied {
+            return self.data.clone();
+        }
+        let mut data = self.data.clone();
+        for c in data.chunks_exact_mut(4) {
+            c.copy_from_slice(&unpremultiply([c[0], c[1], c[2], c[3]]));
+        }
+        data
+    }
+
+    // wraps premultiplied data, converting it back when premultiplied is false
+    fn from_premultiplied(width: u32, height: u32, data: Vec<u8>, premultiplied: bool) -> Self {
+        let mut pixels = PixelsRgba {
+            width,
+            height,
+            data,
+            premultiplied: true,
+        };
+        if !premultiplied {
+            pixels.unpremultiply();
+        }
+        pixels
+    }
+
+    // png stores straight alpha
+    pub fn save_image(&self, name: impl AsRef<Path>) -> Result<(), ColorError> {
+        let file = File::create(name)?;
+        let w = BufWriter::new(file);
+
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+
+        writer.write_image_data(&self.straight_data())?;
+        Ok(())
+    }
+
+    // drops the alpha byte of the straight colors
+    pub fn to_rgb(&self) -> Pixels {
+        let mut data = Vec::with_capacity(self.data.len() / 4 * 3);
+        for c in self.straight_data().chunks_exact(4) {
+            data.extend_from_slice(&c[..3]);
+        }
+        Pixels {
+            width: self.width,
+            height: self.height,
+            data,
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * self.width + x) * 4) as usize;
+        let mut rgba = [0; 4];
+        rgba.copy_from_slice(&self.data[index..index + 4]);
+        rgba
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        let index = ((y * self.width + x) * 4) as usize;
+        self.data[index..index + 4].copy_from_slice(&rgba);
+    }
+
+    // porter-duff source-over in premultiplied space, so transparent pixels
+    // don't bleed their color into the edges. the result is stored like self
+    pub fn over(&self, below: &PixelsRgba) -> Result<PixelsRgba, ColorError> {
+        if (self.width, self.height) != (below.width, below.height) {
+            return Err(ColorError::SizeMismatch {
+                expected: (self.width, self.height),
+                found: (below.width, below.height),
+            });
+        }
+        let premultiplied = |c: &[u8], stored: bool| {
+            let a = c[3] as f32 / 255.0;
+            let scale = if stored { 1.0 } else { a };
+            [
+                c[0] as f32 * scale,
+                c[1] as f32 * scale,
+                c[2] as f32 * scale,
+                a,
+            ]
+        };
+        let mut data = Vec::with_capacity(self.data.len());
+        for (top, bottom) in self.data.chunks_exact(4).zip(below.data.chunks_exact(4)) {
+            let top = premultiplied(top, self.premultiplied);
+            let bottom = premultiplied(bottom, below.premultiplied);
+            let a = top[3] + bottom[3] * (1.0 - top[3]);
+            for i in 0..3 {
+                let c = top[i] + bottom[i] * (1.0 - top[3]);
+                let c = match (self.premultiplied, a > 0.0) {
+                    (true, _) => c,
+                    (false, true) => c / a,
+                    (false, false) => 0.0,
+                };
+                data.push(f32_to_u8_clamped(c));
+            }
+            data.push(f32_to_u8_clamped(a * 255.0));
+        }
+        Ok(PixelsRgba {
+            width: self.width,
+            height: self.height,
+            data,
+            premultiplied: self.premultiplied,
+        })
+    }
+
+    // averages in premultiplied space, windows shrink at the edges like Pixels::box_blur
+    pub fn box_blur(&mut self, radius: u32) {
+        if radius == 0 || self.width == 0 || self.height == 0 {
+            return;
+        }
+        let radius = radius.min(self.width.max(self.height));
+        let source = self.premultiplied_data();
+        let mut data = vec![0; source.len()];
+        for y in 0..self.height {
+            let y0 = y.saturating_sub(radius);
+            let y1 = (y + radius).min(self.height - 1);
+            for x in 0..self.width {
+                let x0 = x.saturating_sub(radius);
+                let x1 = (x + radius).min(self.width - 1);
+                let mut sum = [0u32; 4];
+                for sy in y0..=y1 {
+                    for sx in x0..=x1 {
+                        let index = ((sy * self.width + sx) * 4) as usize;
+                        for (c, s) in sum.iter_mut().enumerate() {
+                            *s += source[index + c] as u32;
+                        }
+                    }
+                }
+                let count = (x1 - x0 + 1) * (y1 - y0 + 1);
+                let index = ((y * self.width + x) * 4) as usize;
+                for (c, s) in sum.iter().enumerate() {
+                    data[index + c] = (s / count) as u8;
+                }
+            }
+        }
+        *self = Self::from_premultiplied(self.width, self.height, data, self.premultiplied);
+    }
+
+    // same sampling as Pixels::resize_bilinear, interpolated in premultiplied space
+    pub fn resize_bilinear(&self, new_w: u32, new_h: u32) -> PixelsRgba {
+        if new_w == 0 || new_h == 0 || self.width == 0 || self.height == 0 {
+            let mut out = PixelsRgba::new(new_w, new_h);
+            out.premultiplied = self.premultiplied;
+            return out;
+        }
+        let source = self.premultiplied_data();
+        let texel = |x: u32, y: u32| ((y * self.width + x) * 4) as usize;
+        let sample = |d: u32, dst: u32, src: u32| {
+            let s = ((d as f32 + 0.5) * src as f32 / dst as f32 - 0.5).clamp(0.0, (src - 1) as f32);
+            let s0 = s.floor() as u32;
+            (s0, (s0 + 1).min(src - 1), s - s0 as f32)
+        };
+        let mut data = Vec::with_capacity((new_w * new_h * 4) as usize);
+        for y in 0..new_h {
+            let (y0, y1, fy) = sample(y, new_h, self.height);
+            for x in 0..new_w {
+                let (x0, x1, fx) = sample(x, new_w, self.width);
+                let (a, b) = (texel(x0, y0), texel(x1, y0));
+                let (c, d) = (texel(x0, y1), texel(x1, y1));
+                for i in 0..4 {
+                    let lerp = |p: usize, q: usize| {
+                        source[p + i] as f32 + (source[q + i] as f32 - source[p + i] as f32) * fx
+                    };
+                    let (top, bottom) = (lerp(a, b), lerp(c, d));
+                    data.push(f32_to_u8_clamped(top + (bottom - top) * fy));
+                }
+            }
+        }
+        Self::from_premultiplied(new_w, new_h, data, self.premultiplied)
+    }
+}
+
+// 16 bits per channel for smooth gradients
+#[cfg(feature = "std")]
+pub struct Pixels16 {
+    width: u32,
+    height: u32,
+    data: Vec<u16>,
+}
+
+#[cfg(feature = "std")]
+impl Pixels16 {
+    pub fn new(width: u32, height: u32) -> Self {
+        let size = (width * height * 3) as usize;
+        let data = vec![u16::MAX; size];
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn save_image(&self, name: impl AsRef<Path>) -> Result<(), ColorError> {
+        let file = File::create(name)?;
+        let w = BufWriter::new(file);
+
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set_color(png::ColorType::RGB);
+        encoder.set_depth(png::BitDepth::Sixteen);
+        let mut writer = encoder.write_header()?;
+
+        // png stores 16 bit samples big-endian
+        let bytes: Vec<u8> = self.data.iter().flat_map(|c| c.to_be_bytes()).collect();
+        writer.write_image_data(&bytes)?;
+        Ok(())
+    }
+
+    // rounds each channel to the nearest 8 bit value
+    pub fn to_rgb(&self) -> Pixels {
+        Pixels {
+            width: self.width,
+            height: self.height,
+            data: self
+                .data
+                .iter()
+                .map(|&c| ((c as u32 + 128) / 257) as u8)
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> [u16; 3] {
+        let index = ((y * self.width + x) * 3) as usize;
+        let mut rgb = [0; 3];
+        rgb.copy_from_slice(&self.data[index..index + 3]);
+        rgb
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, rgb: [u16; 3]) {
+        let index = ((y * self.width + x) * 3) as usize;
+        self.data[index..index + 3].copy_from_slice(&rgb);
+    }
+}
+
+// runs f on every row of packed rgb data, in parallel with the rayon feature
+#[cfg(feature = "std")]
+fn for_each_row(data: &mut [u8], width: u32, f: impl Fn(usize, &mut [u8]) + Send + Sync) {
+    let stride = width as usize * 3;
+    if stride == 0 {
+        return;
+    }
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        data.par_chunks_mut(stride)
+            .enumerate()
+            .for_each(|(y, row)| f(y, row));
+    }
+    #[cfg(not(feature = "rayon"))]
+    data.chunks_mut(stride)
+        .enumerate()
+        .for_each(|(y, row)| f(y, row));
+}
+
+// splitmix64 finalizer over the lattice coordinates, mapped to 0.0..=1.0
+#[cfg(feature = "std")]
+fn lattice_value(seed: u64, x: i64, y: i64) -> f32 {
+    let mut z = seed
+        .wrapping_add((x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+        .wrapping_add((y as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    (z >> 40) as f32 / ((1u64 << 24) - 1) as f32
+}
+
+// bilinear blend of the four surrounding lattice values with smoothstep weights
+#[cfg(feature = "std")]
+fn value_noise_2d(seed: u64, x: f32, y: f32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (ix, iy) = (x0 as i64, y0 as i64);
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+    let top = lattice_value(seed, ix, iy) * (1.0 - tx) + lattice_value(seed, ix + 1, iy) * tx;
+    let bottom =
+        lattice_value(seed, ix, iy + 1) * (1.0 - tx) + lattice_value(seed, ix + 1, iy + 1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+// visits every point on the line from (x0, y0) to (x1, y1)
+#[cfg(feature = "std")]
+fn bresenham(x0: i32, y0: i32, x1: i32, y1: i32, mut f: impl FnMut(i32, i32)) {
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let (mut x, mut y) = (x0, y0);
+    let mut err = dx + dy;
+    loop {
+        f(x, y);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+// visits the first quadrant of an axis-aligned ellipse, the other quadrants
+// are mirror images. the decision variables are scaled by 4 to stay integer
+#[cfg(feature = "std")]
+fn midpoint_ellipse(rx: u32, ry: u32, mut f: impl FnMut(i32, i32)) {
+    if ry == 0 {
+        for x in 0..=rx as i32 {
+            f(x, 0);
+        }
+        return;
+    }
+    let (rx2, ry2) = (rx as i64 * rx as i64, ry as i64 * ry as i64);
+    let (mut x, mut y) = (0i64, ry as i64);
+    let (mut px, mut py) = (0, 2 * rx2 * y);
+    let mut p = 4 * ry2 - 4 * rx2 * ry as i64 + rx2;
+    while px < py {
+        f(x as i32, y as i32);
+        x += 1;
+        px += 2 * ry2;
+        if p < 0 {
+            p += 4 * (ry2 + px);
+        } else {
+            y -= 1;
+            py -= 2 * rx2;
+            p += 4 * (ry2 + px - py);
+        }
+    }
+    p = ry2 * (2 * x + 1) * (2 * x + 1) + 4 * rx2 * (y - 1) * (y - 1) - 4 * rx2 * ry2;
+    while y >= 0 {
+        f(x as i32, y as i32);
+        y -= 1;
+        py -= 2 * rx2;
+        if p > 0 {
+            p += 4 * (rx2 - py);
+        } else {
+            x += 1;
+            px += 2 * ry2;
+            p += 4 * (rx2 - py + px);
+        }
+    }
+}
+
+// channel with the largest spread and its range
+#[cfg(feature = "std")]
+fn widest_channel(colors: &[RGB]) -> (usize, u8) {
+    (0..=2)
+        .map(|i| {
+            let lo = colors.iter().map(|c| c[i]).min().unwrap_or(0);
+            let hi = colors.iter().map(|c| c[i]).max().unwrap_or(0);
+            (i, hi - lo)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap()
+}
+
+// median cut: split the box with the widest channel at its median until there are n boxes
+#[cfg(feature = "std")]
+pub fn quantize(pixels: &Pixels, n: usize) -> Vec<RGB> {
+    let colors: Vec<RGB> = pixels
+        .data
+        .chunks_exact(3)
+        .map(|c| RGB::new(c[0], c[1], c[2]))
+        .collect();
+    if n == 0 || colors.is_empty() {
+        return vec![];
+    }
+    let mut boxes = vec![colors];
+    while boxes.len() < n {
+        let (index, (channel, range)) = boxes
+            .iter()
+            .map(|b| widest_channel(b))
+            .enumerate()
+            .max_by_key(|&(_, (_, range))| range)
+            .unwrap();
+        if range == 0 {
+            break;
+        }
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|c| c[channel]);
+        let upper = colors.split_off(colors.len() / 2);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+    boxes.iter().map(|b| average(b)).collect()
+}
+
+// GIMP palette with the hex value as the name of every color
+#[cfg(feature = "std")]
+pub fn save_gpl(
+    colors: &[RGB],
+    name: impl AsRef<Path>,
+    palette_name: &str,
+) -> Result<(), ColorError> {
+    let file = File::create(name)?;
+    let mut w = BufWriter::new(file);
+    writeln!(w, "GIMP Palette")?;
+    writeln!(w, "Name: {}", palette_name)?;
+    writeln!(w, "#")?;
+    for color in colors {
+        writeln!(
+            w,
+            "{:3} {:3} {:3}\t{}",
+            color.red, color.green, color.blue, color
+        )?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+pub fn to_css_variables(colors: &[RGB], prefix: &str) -> String {
+    let mut css = String::from(":root {\n");
+    for (i, color) in colors.iter().enumerate() {
+        css.push_str(&format!("  --{}-{}: {};\n", prefix, i, color));
+    }
+    css.push_str("}\n");
+    css
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum AseError {
+    Io(std::io::Error),
+    InvalidSignature,
+    Truncated,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for AseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AseError::Io(err) => write!(f, "{}", err),
+            AseError::InvalidSignature => write!(f, "missing ASEF signature"),
+            AseError::Truncated => write!(f, "unexpected end of swatch data"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AseError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for AseError {
+    fn from(err: std::io::Error) -> Self {
+        AseError::Io(err)
+    }
+}
+
+#[cfg(feature = "std")]
+struct AseReader<'a> {
+    bytes: &'a [u8],
+}
+
+#[cfg(feature = "std")]
+impl<'a> AseReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], AseError> {
+        if self.bytes.len() < n {
+            return Err(AseError::Truncated);
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> Result<u16, AseError> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, AseError> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, AseError> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+}
+
+// Adobe Swatch Exchange: RGB, CMYK and Gray entries are converted, LAB entries are skipped
+#[cfg(feature = "std")]
+pub fn parse_ase(bytes: &[u8]) -> Result<Vec<RGB>, AseError> {
+    let mut reader = AseReader { bytes };
+    if reader.take(4)? != b"ASEF" {
+        return Err(AseError::InvalidSignature);
+    }
+    let _version = (reader.u16()?, reader.u16()?);
+    let blocks = reader.u32()?;
+
+    let channel = |v: f32| f32_to_u8_clamped(v * 255.0);
+    let mut colors = vec![];
+    for _ in 0..blocks {
+        let kind = reader.u16()?;
+        let length = reader.u32()? as usize;
+        let mut block = AseReader {
+            bytes: reader.take(length)?,
+        };
+        if kind != 0x0001 {
+            continue;
+        }
+        let name_length = block.u16()? as usize;
+        block.take(name_length * 2)?;
+        let model = block.take(4)?;
+        let color = match model {
+            b"RGB " => RGB::new(
+                channel(block.f32()?),
+                channel(block.f32()?),
+                channel(block.f32()?),
+            ),
+            b"CMYK" => {
+                let (c, m, y, k) = (block.f32()?, block.f32()?, block.f32()?, block.f32()?);
+                RGB::new(
+                    channel((1.0 - c) * (1.0 - k)),
+                    channel((1.0 - m) * (1.0 - k)),
+                    channel((1.0 - y) * (1.0 - k)),
+                )
+            }
+            b"Gray" => {
+                let value = channel(block.f32()?);
+                RGB::new(value, value, value)
+            }
+            _ => continue,
+        };
+        colors.push(color);
+    }
+    Ok(colors)
+}
+
+#[cfg(feature = "std")]
+pub fn load_ase(path: impl AsRef<Path>) -> Result<Vec<RGB>, AseError> {
+    parse_ase(&std::fs::read(path)?)
+}
+
+// looping animation with a palette quantized from all frames together
+#[cfg(feature = "gif")]
+pub fn save_gif(
+    frames: &[Pixels],
+    name: impl AsRef<Path>,
+    delay_cs: u16,
+) -> Result<(), ColorError> {
+    let first = frames.first().ok_or(ColorError::NoFrames)?;
+    if let Some(f) = frames
+        .iter()
+        .find(|f| f.width != first.width || f.height != first.height)
+    {
+        return Err(ColorError::SizeMismatch {
+            expected: (first.width, first.height),
+            found: (f.width, f.height),
+        });
+    }
+    let too_large = || ColorError::TooLarge {
+        width: first.width,
+        height: first.height,
+    };
+    let width = u16::try_from(first.width).map_err(|_| too_large())?;
+    let height = u16::try_from(first.height).map_err(|_| too_large())?;
+
+    let all = Pixels {
+        width: first.width,
+        height: first.height * frames.len() as u32,
+        data: frames.iter().flat_map(|f| f.data.iter().copied()).collect(),
+    };
+    let palette = quantize(&all, 256);
+    let flat: Vec<u8> = palette.iter().flat_map(|&c| <[u8; 3]>::from(c)).collect();
+
+    let file = File::create(name)?;
+    let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &flat)?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    let lab_palette = LabPalette::new(&palette);
+    let mut lookup = HashMap::new();
+    for frame in frames {
+        let indices: Vec<u8> = frame
+            .data
+            .chunks_exact(3)
+            .map(|c| {
+                let color = RGB::new(c[0], c[1], c[2]);
+                *lookup
+                    .entry(color)
+                    .or_insert_with(|| lab_palette.nearest_index(color).unwrap() as u8)
+            })
+            .collect();
+        let mut frame = gif::Frame::from_indexed_pixels(width, height, indices, None);
+        frame.delay = delay_cs;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+// hue runs counterclockwise from red at the right, saturation grows from white at the center
+#[cfg(feature = "std")]
+pub fn color_wheel(size: u32) -> Pixels {
+    let mut pixels = Pixels::new(size, size);
+    let radius = size as f32 / 2.0;
+    for y in 0..size {
+        for x in 0..size {
+            let dx = x as f32 + 0.5 - radius;
+            let dy = radius - (y as f32 + 0.5);
+            let r = (dx * dx + dy * dy).sqrt() / radius;
+            if r > 1.0 {
+                continue;
+            }
+            let angle = dy.atan2(dx).to_degrees().rem_euclid(360.0);
+            let hue = (angle * 10.0).round() as u32 % 3600;
+            let w = ((1.0 - r) * 1000.0).round() as u16;
+            pixels.set(x, y, hwb_to_rgb((hue, w, 0)));
+        }
+    }
+    pixels
+}
+
+#[cfg(feature = "std")]
+pub fn palette(color: HWB) -> Result<(), ColorError> {
+    palette_to(color, Path::new("images"))
+}
+
+// steps x steps colors of whiteness (rows) against blackness (columns)
+#[cfg(feature = "std")]
+pub fn palette_grid(color: HWB, steps: usize) -> Vec<Vec<RGB>> {
+    let last = steps.saturating_sub(1).max(1);
+    (0..steps)
+        .map(|w| {
+            (0..steps)
+                .map(|b| {
+                    let w = (1000 * w) / last;
+                    let b = (1000 * b) / last;
+                    hwb_to_rgb((color.0, w as u16, b as u16))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// palette_grid drawn as cell x cell squares
+#[cfg(feature = "std")]
+pub fn palette_sized(color: HWB, steps: usize, cell: u32) -> Pixels {
+    let size = steps as u32 * cell;
+    let mut pixels = Pixels::new(size, size);
+    for (w, row) in palette_grid(color, steps).iter().enumerate() {
+        for (b, &rgb) in row.iter().enumerate() {
+            pixels.rect(b as u32 * cell, w as u32 * cell, cell, cell, rgb);
+        }
+    }
+    pixels
+}
+
+#[cfg(feature = "std")]
+pub fn palette_to(color: HWB, dir: &Path) -> Result<(), ColorError> {
+    let pixels = palette_sized(color, 8, 16);
+    fs::create_dir_all(dir)?;
+    let name = dir.join(format!("palette{}.png", color.0 / 10));
+    pixels.save_image(name)
+}
+
+// one frame per saturation level, hues across and lightness levels down in HSL
+#[cfg(feature = "std")]
+pub fn saturation_grid(hue_step: usize, sat_levels: u32, lum_levels: u32) -> Vec<Pixels> {
+    const SIZE: u32 = 16;
+    let hues: Vec<u32> = (0..360).step_by(hue_step.max(1)).collect();
+    (0..=sat_levels)
+        .map(|s| {
+            let mut pixels = Pixels::new(hues.len() as u32 * SIZE, (lum_levels + 1) * SIZE);
+            let s = 1000 * s / sat_levels.max(1);
+            for (i, &hue) in hues.iter().enumerate() {
+                for l in 0..=lum_levels {
+                    let lightness = 1000 * l / lum_levels.max(1);
+                    let rgb = hsl_to_rgb((hue * 10, s as u16, lightness as u16));
+                    pixels.rect(i as u32 * SIZE, l * SIZE, SIZE, SIZE, rgb);
+                }
+            }
+            pixels
+        })
+        .collect()
+}
+
+#[cfg(feature = "std")]
+pub fn mandelbrot(width: u32, height: u32, max_iter: u32) -> Pixels {
+    mandelbrot_view(width, height, max_iter, (-0.75, 0.0), 3.5)
+}
+
+// span is the width of the view on the real axis, pixels stay square
+#[cfg(feature = "std")]
+pub fn mandelbrot_view(
+    width: u32,
+    height: u32,
+    max_iter: u32,
+    center: (f64, f64),
+    span: f64,
+) -> Pixels {
+    let mut pixels = Pixels::new(width, height);
+    let scale = span / width.max(1) as f64;
+    for y in 0..height {
+        let ci = center.1 + (y as f64 + 0.5 - height as f64 / 2.0) * scale;
+        for x in 0..width {
+            let cr = center.0 + (x as f64 + 0.5 - width as f64 / 2.0) * scale;
+            let (mut zr, mut zi) = (0.0f64, 0.0f64);
+            let mut n = 0;
+            while n < max_iter && zr * zr + zi * zi <= 4.0 {
+                let t = zr * zr - zi * zi + cr;
+                zi = 2.0 * zr * zi + ci;
+                zr = t;
+                n += 1;
+            }
+            let rgb = if n == max_iter {
+                RGB::BLACK
+            } else {
+                hue_to_rgb((n as u64 * 3600 / max_iter as u64) as u32)
+            };
+            pixels.set(x, y, rgb);
+        }
+    }
+    pixels
+}
+
+#[cfg(feature = "std")]
+pub fn hue_palette() -> Result<(), ColorError> {
+    hue_palette_to(Path::new("images"))
+}
+
+#[cfg(feature = "std")]
+pub fn hue_palette_to(dir: &Path) -> Result<(), ColorError> {
+    const SCALE: usize = 4;
+    const SIZE: u32 = 1 << SCALE;
+
+    const WIDTH_STEPS: u32 = 360 / 15;
+    const HEIGHT_STEPS: u32 = 200 / 20;
+
+    let mut pixels = Pixels::new(WIDTH_STEPS << SCALE, HEIGHT_STEPS << SCALE);
+    for hue in (0..360).step_by(15) {
+        for value in (0..200).step_by(20) {
+            let x = (hue / 15) << SCALE;
+            let y = ((value / 20) << SCALE) as u32;
+            let b = 100 - (value + 10).min(100);
+            let w = (value as i16 - 100).max(0);
+            let rgb = hwb_to_rgb((hue * 10, w as u16 * 10, b as u16 * 10));
+            pixels.rect(x, y, SIZE, SIZE, rgb);
+        }
+    }
+    fs::create_dir_all(dir)?;
+    pixels.save_image(dir.join("hue_palette.png"))
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb() {
+        assert_eq!(rgb(0xcc3366), RGB::new(0xcc, 0x33, 0x66));
+        assert_eq!(rgb(0xcc3366).to_string(), "#cc3366");
+        assert_eq!(RGB::new(0, 10, 255).to_string(), "#000aff");
+        assert_eq!(RGB::from([0xcc, 0x33, 0x66]), rgb(0xcc3366));
+        let array: [u8; 3] = rgb(0xcc3366).into();
+        assert_eq!(array, [0xcc, 0x33, 0x66]);
+        assert_eq!(rgb(0xcc3366)[2], 0x66);
+        assert_eq!(hwb_to_rgb((0, 200, 200)).to_string(), "#cc3333");
+    }
+
+    #[test]
+    fn test_rgb_const() {
+        const ACCENT: RGB = rgb(0xff8800);
+        static TABLE: [RGB; 2] = [rgb(0x000000), rgb(0x123456)];
+        assert_eq!(ACCENT, RGB::new(0xff, 0x88, 0x00));
+        assert_eq!(TABLE[1], RGB::new(0x12, 0x34, 0x56));
+    }
+
+    #[test]
+    fn test_rgb_from() {
+        assert_eq!(RGB::from(0xff8800), RGB::new(0xff, 0x88, 0x00));
+        assert_eq!(RGB::from(0x000000), RGB::new(0, 0, 0));
+        let color: RGB = 0x123456.into();
+        assert_eq!(color, RGB::new(0x12, 0x34, 0x56));
+        assert_eq!(RGB::from([0x12, 0x34, 0x56]), color);
+        assert_eq!(<[u8; 3]>::from(color), [0x12, 0x34, 0x56]);
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!("#cc3366".parse(), Ok(rgb(0xcc3366)));
+        assert_eq!("CC3366".parse(), Ok(rgb(0xcc3366)));
+        assert_eq!("#c36".parse(), Ok(rgb(0xcc3366)));
+        assert_eq!("fff".parse(), Ok(rgb(0xffffff)));
+        assert!("#cc336".parse::<RGB>().is_err());
+        assert!("#+c3366".parse::<RGB>().is_err());
+        assert!("#gg0000".parse::<RGB>().is_err());
+        assert!("".parse::<RGB>().is_err());
+        let color = rgb(0x0a0b0c);
+        assert_eq!(color.to_string().parse(), Ok(color));
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(RGB::default(), rgb(0x000000));
+        assert_eq!(RGB::BLACK, rgb(0x000000));
+        assert_eq!(RGB::WHITE, rgb(0xffffff));
+        assert_eq!(RGB::RED, rgb(0xff0000));
+        assert_eq!(RGB::GREEN, rgb(0x00ff00));
+        assert_eq!(RGB::BLUE, rgb(0x0000ff));
+        assert_eq!(RGB::YELLOW, rgb(0xffff00));
+        assert_eq!(RGB::CYAN, rgb(0x00ffff));
+        assert_eq!(RGB::MAGENTA, rgb(0xff00ff));
+    }
+
+    #[test]
+    fn test_rgb_methods() {
+        assert_eq!(RGB::from_hwb((0, 0, 0)).to_hwb(), (0, 0, 0));
+        let color = rgb(0xcc3366);
+        assert_eq!(color.to_hwb(), rgb_to_hwb(color));
+        assert_eq!(color.to_hsl(), rgb_to_hsl(color));
+        assert_eq!(color.to_hsv(), rgb_to_hsv(color));
+        assert_eq!(RGB::from_hsl((1200, 1000, 500)), RGB::GREEN);
+        assert_eq!(RGB::from_hsv((2400, 1000, 1000)), RGB::BLUE);
+    }
+
+    #[test]
+    fn test_parse_css_hwb() {
+        assert_eq!(parse_css_hwb("hwb(0 0% 0%)"), Ok((0, 0, 0)));
+        assert_eq!(parse_css_hwb("hwb(120 20% 30%)"), Ok((1200, 200, 300)));
+        assert_eq!(parse_css_hwb("hwb(120, 20%, 30%)"), Ok((1200, 200, 300)));
+        assert_eq!(parse_css_hwb(" hwb(90.5deg 12.5% 0%) "), Ok((905, 125, 0)));
+        assert_eq!(parse_css_hwb("hwb(-30 0% 150%)"), Ok((3300, 0, 1000)));
+        assert!(parse_css_hwb("hwb(120 20% 30)").is_err());
+        assert!(parse_css_hwb("hwb(120 20%)").is_err());
+        assert!(parse_css_hwb("hwb(120 20% 30% 40%)").is_err());
+        assert!(parse_css_hwb("hsl(120 20% 30%)").is_err());
+        assert!(parse_css_hwb("hwb(nan 20% 30%)").is_err());
+        assert_eq!(parse_css_hwb("HWB(0 0% 0%)"), Ok((0, 0, 0)));
+        assert_eq!(parse_css_hwb("Hwb(120DEG 20% 30%)"), Ok((1200, 200, 300)));
+        assert_eq!(
+            parse_css_hwb("HWB(1 2)"),
+            Err(ParseColorError("HWB(1 2)".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_model_display() {
+        assert_eq!(Hwb(0, 0, 0).to_string(), "hwb(0 0% 0%)");
+        assert_eq!(
+            Hwb::from((905, 125, 300)).to_string(),
+            "hwb(90.5 12.5% 30%)"
+        );
+        assert_eq!(Hsl(1200, 1000, 500).to_string(), "hsl(120 100% 50%)");
+        assert_eq!(Hsv(2400, 1000, 1000).to_string(), "hsv(240 100% 100%)");
+        let hwb: HWB = Hwb::from(RGB::RED.to_hwb()).into();
+        assert_eq!(hwb, RGB::RED.to_hwb());
+        assert_eq!(
+            parse_css_hwb(&Hwb(1200, 200, 300).to_string()),
+            Ok((1200, 200, 300))
+        );
+    }
+
+    #[test]
+    fn test_to_hex() {
+        let color = RGB::new(0xab, 0xcd, 0xef);
+        assert_eq!(color.to_hex(false, true), "#abcdef");
+        assert_eq!(color.to_hex(true, true), "#ABCDEF");
+        assert_eq!(color.to_hex(false, false), "abcdef");
+        assert_eq!(color.to_hex(true, false), "ABCDEF");
+        assert_eq!(color.to_hex(false, true), color.to_string());
+        assert_eq!(RGB::new(0, 10, 255).to_hex(true, false), "000AFF");
+    }
+
+    #[test]
+    fn test_parse_hex_rgba() {
+        assert_eq!(parse_hex_rgba("#ff000080"), Ok([255, 0, 0, 128]));
+        assert_eq!(parse_hex_rgba("#f00f"), Ok([255, 0, 0, 255]));
+        assert_eq!(parse_hex_rgba("0f08"), Ok([0, 255, 0, 136]));
+        assert_eq!(parse_hex_rgba("#cc3366"), Ok([0xcc, 0x33, 0x66, 255]));
+        assert_eq!(parse_hex_rgba("#c36"), Ok([0xcc, 0x33, 0x66, 255]));
+        assert!(parse_hex_rgba("#ff00008").is_err());
+        assert!(parse_hex_rgba("#ff00008g").is_err());
+        assert!("#ff000080".parse::<RGB>().is_err());
+        assert!("#f00f".parse::<RGB>().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let color = rgb(0xcc3366);
+        let json = serde_json::to_string(&color).unwrap();
+        assert_eq!(json, "\"#cc3366\"");
+        assert_eq!(serde_json::from_str::<RGB>(&json).unwrap(), color);
+        assert_eq!(serde_json::from_str::<RGB>("\"#c36\"").unwrap(), color);
+        assert!(serde_json::from_str::<RGB>("\"#cc33\"").is_err());
+        let palette: Vec<RGB> = serde_json::from_str("[\"#000000\", \"ffffff\"]").unwrap();
+        assert_eq!(palette, vec![rgb(0x000000), rgb(0xffffff)]);
+    }
+
+    #[test]
+    fn test_rgb_hash() {
+        let colors: std::collections::HashSet<RGB> =
+            vec![rgb(0xff0000), rgb(0x00ff00), rgb(0xff0000), hue_to_rgb(0)]
+                .into_iter()
+                .collect();
+        assert_eq!(colors.len(), 2);
+        assert!(colors.contains(&rgb(0x00ff00)));
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(rgb_to_hwb(rgb(0xff0000)), (0, 0, 0));
+        assert_eq!(rgb_to_hwb(rgb(0xff8000)), (301, 0, 0));
+        assert_eq!(rgb_to_hwb(rgb(0x00ff00)), (1200, 0, 0));
+        assert_eq!(rgb_to_hwb(rgb(0x0000ff)), (2400, 0, 0));
+        assert_eq!(rgb_to_hwb(rgb(0xffff00)), (600, 0, 0));
+        assert_eq!(rgb_to_hwb(rgb(0x00ffff)), (1800, 0, 0));
+        assert_eq!(rgb_to_hwb(rgb(0xff00ff)), (3000, 0, 0));
+
+        assert_eq!(rgb_to_hwb(rgb(0xcc3333)), (0, 200, 200));
+        assert_eq!(rgb_to_hwb(rgb(0x808080)), (0, 501, 498));
+    }
+
+    #[test]
+    fn test_hue_to_rgb() {
+        assert_eq!(hue_to_rgb(0), rgb(0xff0000));
+        assert_eq!(hue_to_rgb(600), rgb(0xffff00));
+        assert_eq!(hue_to_rgb(1200), rgb(0x00ff00));
+        assert_eq!(hue_to_rgb(1800), rgb(0x00ffff));
+        assert_eq!(hue_to_rgb(2400), rgb(0x0000ff));
+        assert_eq!(hue_to_rgb(3000), rgb(0xff00ff));
+    }
+
+    #[test]
+    fn test_hwb_wrap_hue() {
+        assert_eq!(hwb_to_rgb((3600, 0, 0)), hwb_to_rgb((0, 0, 0)));
+        assert_eq!(hwb_to_rgb((4200, 0, 0)), hwb_to_rgb((600, 0, 0)));
+        assert_eq!(
+            hwb_to_rgb((3600 * 100 + 1200, 200, 0)),
+            hwb_to_rgb((1200, 200, 0))
+        );
+        assert_eq!(
+            hwb_to_rgb((u32::MAX, 0, 0)),
+            hwb_to_rgb((u32::MAX % 3600, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_hwb_block_red() {
+        assert_eq!(hwb_to_rgb((0, 0, 0)), rgb(0xff0000));
+        assert_eq!(hwb_to_rgb((0, 200, 0)), rgb(0xff3333));
+        assert_eq!(hwb_to_rgb((0, 400, 0)), rgb(0xff6666));
+        assert_eq!(hwb_to_rgb((0, 600, 0)), rgb(0xff9999));
+        assert_eq!(hwb_to_rgb((0, 800, 0)), rgb(0xffcccc));
+        assert_eq!(hwb_to_rgb((0, 1000, 0)), rgb(0xffffff));
+
+        assert_eq!(hwb_to_rgb((0, 0, 0)), rgb(0xff0000));
+        assert_eq!(hwb_to_rgb((0, 0, 200)), rgb(0xcc0000));
+        assert_eq!(hwb_to_rgb((0, 0, 400)), rgb(0x990000));
+        assert_eq!(hwb_to_rgb((0, 0, 600)), rgb(0x660000));
+        assert_eq!(hwb_to_rgb((0, 0, 800)), rgb(0x330000));
+        assert_eq!(hwb_to_rgb((0, 0, 1000)), rgb(0x000000));
+
+        assert_eq!(hwb_to_rgb((0, 0, 1000)), rgb(0x000000));
+        assert_eq!(hwb_to_rgb((0, 200, 1000)), rgb(0x2a2a2a));
+        assert_eq!(hwb_to_rgb((0, 400, 1000)), rgb(0x484848));
+        assert_eq!(hwb_to_rgb((0, 600, 1000)), rgb(0x5f5f5f));
+        assert_eq!(hwb_to_rgb((0, 800, 1000)), rgb(0x717171));
+        assert_eq!(hwb_to_rgb((0, 1000, 1000)), rgb(0x7f7f7f));
+
+        assert_eq!(hwb_to_rgb((0, 1000, 1000)), rgb(0x7f7f7f));
+        assert_eq!(hwb_to_rgb((0, 1000, 800)), rgb(0x8d8d8d));
+        assert_eq!(hwb_to_rgb((0, 1000, 600)), rgb(0x9f9f9f));
+        assert_eq!(hwb_to_rgb((0, 1000, 400)), rgb(0xb6b6b6));
+        assert_eq!(hwb_to_rgb((0, 1000, 200)), rgb(0xd4d4d4));
+        assert_eq!(hwb_to_rgb((0, 1000, 0)), rgb(0xffffff));
+
+        assert_eq!(hwb_to_rgb((0, 0, 200)), rgb(0xcc0000));
+        assert_eq!(hwb_to_rgb((0, 200, 200)), rgb(0xcc3333));
+        assert_eq!(hwb_to_rgb((0, 400, 200)), rgb(0xcc6666));
+        assert_eq!(hwb_to_rgb((0, 600, 200)), rgb(0xcc9999));
+        assert_eq!(hwb_to_rgb((0, 800, 200)), rgb(0xcccccc));
+        assert_eq!(hwb_to_rgb((0, 1000, 200)), rgb(0xd4d4d4));
+
+        assert_eq!(hwb_to_rgb((0, 0, 400)), rgb(0x990000));
+        assert_eq!(hwb_to_rgb((0, 200, 400)), rgb(0x993333));
+        assert_eq!(hwb_to_rgb((0, 400, 400)), rgb(0x996666));
+        assert_eq!(hwb_to_rgb((0, 600, 400)), rgb(0x999999));
+        assert_eq!(hwb_to_rgb((0, 800, 400)), rgb(0xa9a9a9));
+        assert_eq!(hwb_to_rgb((0, 1000, 400)), rgb(0xb6b6b6));
+
+        assert_eq!(hwb_to_rgb((0, 0, 600)), rgb(0x660000));
         assert_eq!(hwb_to_rgb((0, 200, 600)), rgb(0x663333));
         assert_eq!(hwb_to_rgb((0, 400, 600)), rgb(0x666666));
         assert_eq!(hwb_to_rgb((0, 600, 600)), rgb(0x7f7f7f));
         assert_eq!(hwb_to_rgb((0, 800, 600)), rgb(0x919191));
         assert_eq!(hwb_to_rgb((0, 1000, 600)), rgb(0x9f9f9f));
 
-        assert_eq!(hwb_to_rgb((0, 0, 800)), rgb(0x330000));
-        assert_eq!(hwb_to_rgb((0, 200, 800)), rgb(0x333333));
-        assert_eq!(hwb_to_rgb((0, 400, 800)), rgb(0x545454));
-        assert_eq!(hwb_to_rgb((0, 600, 800)), rgb(0x6d6d6d));
-        assert_eq!(hwb_to_rgb((0, 800, 800)), rgb(0x7f7f7f));
-        assert_eq!(hwb_to_rgb((0, 1000, 800)), rgb(0x8d8d8d));
+        assert_eq!(hwb_to_rgb((0, 0, 800)), rgb(0x330000));
+        assert_eq!(hwb_to_rgb((0, 200, 800)), rgb(0x333333));
+        assert_eq!(hwb_to_rgb((0, 400, 800)), rgb(0x545454));
+        assert_eq!(hwb_to_rgb((0, 600, 800)), rgb(0x6d6d6d));
+        assert_eq!(hwb_to_rgb((0, 800, 800)), rgb(0x7f7f7f));
+        assert_eq!(hwb_to_rgb((0, 1000, 800)), rgb(0x8d8d8d));
+    }
+
+    #[test]
+    fn test_hwb_block_orange() {
+        assert_eq!(hwb_to_rgb((300, 0, 0)), rgb(0xff7f00));
+        assert_eq!(hwb_to_rgb((300, 200, 0)), rgb(0xff9933));
+        assert_eq!(hwb_to_rgb((300, 400, 0)), rgb(0xffb266));
+        assert_eq!(hwb_to_rgb((300, 600, 0)), rgb(0xffcc99));
+        assert_eq!(hwb_to_rgb((300, 800, 0)), rgb(0xffe5cc));
+        assert_eq!(hwb_to_rgb((300, 1000, 0)), rgb(0xffffff));
+    }
+
+    #[test]
+    fn test_gray() {
+        assert_eq!(gray(500), RGB::new(127, 127, 127));
+    }
+
+    #[test]
+    fn test_mix() {
+        assert_eq!(
+            mix(500, RGB::new(255, 0, 127), RGB::new(0, 255, 127)),
+            RGB::new(128, 128, 127)
+        );
+        // the midpoint is symmetric in both directions
+        assert_eq!(mix(500, RGB::BLACK, RGB::WHITE), RGB::new(128, 128, 128));
+        assert_eq!(mix(500, RGB::WHITE, RGB::BLACK), RGB::new(128, 128, 128));
+        assert_eq!(mix(1, RGB::BLACK, RGB::WHITE), RGB::BLACK);
+        assert_eq!(mix(999, RGB::BLACK, RGB::WHITE), RGB::WHITE);
+        // p past 1000 saturates at b
+        assert_eq!(mix(2000, RGB::BLACK, RGB::WHITE), RGB::WHITE);
+        assert_eq!(mix(2000, RGB::WHITE, RGB::BLACK), RGB::BLACK);
+        assert_eq!(mix(u16::MAX, rgb(0xcc3366), rgb(0x123456)), rgb(0x123456));
+    }
+
+    #[test]
+    fn test_hsl() {
+        assert_eq!(rgb_to_hsl(rgb(0xff0000)), (0, 1000, 500));
+        assert_eq!(rgb_to_hsl(rgb(0x00ff00)), (1200, 1000, 500));
+        assert_eq!(rgb_to_hsl(rgb(0xffffff)), (0, 0, 1000));
+        assert_eq!(rgb_to_hsl(rgb(0x000000)), (0, 0, 0));
+
+        assert_eq!(hsl_to_rgb((0, 1000, 500)), rgb(0xff0000));
+        assert_eq!(hsl_to_rgb((1200, 1000, 500)), rgb(0x00ff00));
+        assert_eq!(hsl_to_rgb((2400, 1000, 500)), rgb(0x0000ff));
+        assert_eq!(hsl_to_rgb((0, 1000, 250)), rgb(0x800000));
+        assert_eq!(hsl_to_rgb((0, 0, 1000)), rgb(0xffffff));
+        assert_eq!(hsl_to_rgb((0, 0, 0)), rgb(0x000000));
+    }
+
+    #[test]
+    fn test_hsl_extreme_lightness() {
+        for hue in (0..3600).step_by(150) {
+            for s in (0..=1000).step_by(250) {
+                assert_eq!(hsl_to_rgb((hue, s, 1000)), RGB::WHITE);
+                assert_eq!(hsl_to_rgb((hue, s, 0)), RGB::BLACK);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hsv() {
+        for hue in (0..3600).step_by(600) {
+            assert_eq!(hsv_to_rgb((hue, 1000, 1000)), hsl_to_rgb((hue, 1000, 500)));
+            assert_eq!(hsv_to_rgb((hue, 1000, 0)), rgb(0x000000));
+            assert_eq!(hsv_to_rgb((hue, 0, 0)), rgb(0x000000));
+        }
+        assert_eq!(hsv_to_rgb((0, 0, 1000)), rgb(0xffffff));
+        assert_eq!(hsv_to_rgb((0, 800, 800)), rgb(0xcc2828));
+
+        assert_eq!(rgb_to_hsv(rgb(0xff0000)), (0, 1000, 1000));
+        assert_eq!(rgb_to_hsv(rgb(0x00ff00)), (1200, 1000, 1000));
+        assert_eq!(rgb_to_hsv(rgb(0xffffff)), (0, 0, 1000));
+        assert_eq!(rgb_to_hsv(rgb(0x000000)), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_lighten_darken() {
+        let red = rgb(0xff0000);
+        let light = lighten(red, 200);
+        assert_eq!(light, rgb(0xff6666));
+        for i in 0..=2 {
+            assert!(light[i] >= red[i]);
+        }
+        assert_eq!(lighten(red, 1000), rgb(0xffffff));
+        assert_eq!(darken(red, 200), rgb(0x990000));
+        assert_eq!(darken(red, 1000), rgb(0x000000));
+    }
+
+    #[test]
+    fn test_saturate_desaturate() {
+        let gray = desaturate(rgb(0xcc3333), 1000);
+        assert_eq!(gray, rgb(0x7f7f7f));
+        assert_eq!(desaturate(rgb(0xff0000), 1000), rgb(0x7f7f7f));
+        assert_eq!(saturate(rgb(0xff0000), 500), rgb(0xff0000));
+        assert_eq!(saturate(rgb(0x00ff00), 1000), rgb(0x00ff00));
+        assert_eq!(saturate(rgb(0x996666), 1000), rgb(0xff0000));
+    }
+
+    #[test]
+    fn test_rotate_hue() {
+        assert_eq!(rotate_hue(rgb(0xff0000), 1200), rgb(0x00ff00));
+        assert_eq!(rotate_hue(rgb(0xff0000), -1200), rgb(0x0000ff));
+        assert_eq!(rotate_hue(rgb(0xff0000), -600), rgb(0xff00ff));
+        assert_eq!(rotate_hue(rgb(0xff0000), 3600 + 1200), rgb(0x00ff00));
+        assert_eq!(rotate_hue(rgb(0x808080), 1200), rgb(0x808080));
+    }
+
+    #[test]
+    fn test_invert() {
+        assert_eq!(invert(rgb(0x000000)), rgb(0xffffff));
+        assert_eq!(invert(rgb(0xcc3366)), rgb(0x33cc99));
+        assert_eq!(invert(invert(rgb(0xcc3366))), rgb(0xcc3366));
+
+        let mut pixels = Pixels::new(2, 2);
+        pixels.set(1, 0, rgb(0xcc3366));
+        pixels.invert();
+        assert_eq!(&pixels.data[..6], &[0, 0, 0, 0x33, 0xcc, 0x99]);
+        pixels.invert();
+        assert_eq!(&pixels.data[..6], &[255, 255, 255, 0xcc, 0x33, 0x66]);
+    }
+
+    #[test]
+    fn test_sepia() {
+        let brown = sepia(rgb(0x808080));
+        assert_eq!(brown, RGB::new(172, 153, 119));
+        assert!(brown.red > brown.green && brown.green > brown.blue);
+        assert_eq!(sepia(rgb(0xffffff)), RGB::new(255, 255, 238));
+        assert_eq!(sepia(rgb(0x000000)), rgb(0x000000));
+
+        let mut pixels = Pixels::new(2, 1);
+        pixels.set(0, 0, rgb(0x808080));
+        pixels.sepia();
+        assert_eq!(pixels.get(0, 0), brown);
+        assert_eq!(pixels.get(1, 0), RGB::new(255, 255, 238));
+    }
+
+    #[test]
+    fn test_posterize() {
+        let mut pixels = Pixels::new(256, 1);
+        for x in 0..256 {
+            pixels.set(x, 0, RGB::new(x as u8, 255 - x as u8, 0));
+        }
+        let original = pixels.data.clone();
+        pixels.posterize(1);
+        assert_eq!(pixels.data, original);
+
+        pixels.posterize(2);
+        assert!(pixels.data.iter().all(|&c| c == 0 || c == 255));
+        assert_eq!(pixels.get(127, 0), RGB::new(0, 255, 0));
+        assert_eq!(pixels.get(128, 0), RGB::new(255, 0, 0));
+
+        let mut pixels = Pixels::new(256, 1);
+        for x in 0..256 {
+            pixels.set(x, 0, gray((x * 1000 / 255) as u16));
+        }
+        pixels.posterize(4);
+        let mut values: Vec<u8> = pixels.data.clone();
+        values.sort_unstable();
+        values.dedup();
+        assert_eq!(values, vec![0, 85, 170, 255]);
+    }
+
+    #[test]
+    fn test_brightness_contrast() {
+        let mut pixels = Pixels::new(3, 1);
+        pixels.set(0, 0, rgb(0x000000));
+        pixels.set(1, 0, rgb(0xcc3366));
+        pixels.adjust_brightness(16);
+        assert_eq!(pixels.get(0, 0), rgb(0x101010));
+        assert_eq!(pixels.get(1, 0), rgb(0xdc4376));
+        pixels.adjust_brightness(-32);
+        assert_eq!(pixels.get(0, 0), rgb(0x000000));
+        pixels.adjust_brightness(1000);
+        assert!(pixels.data.iter().all(|&c| c == 255));
+
+        let mut pixels = Pixels::new(3, 1);
+        pixels.set(0, 0, rgb(0x000000));
+        pixels.set(1, 0, rgb(0xcc3366));
+        let original = pixels.data.clone();
+        pixels.adjust_contrast(1.0);
+        assert_eq!(pixels.data, original);
+        pixels.adjust_contrast(2.0);
+        assert_eq!(pixels.get(1, 0), RGB::new(255, 0, 76));
+        pixels.adjust_contrast(0.0);
+        assert!(pixels.data.iter().all(|&c| c == 128));
+    }
+
+    #[test]
+    fn test_pixels_rotate_hue() {
+        let mut pixels = Pixels::new(4, 4);
+        pixels.rect(0, 0, 4, 4, rgb(0xff0000));
+        pixels.set(3, 3, rgb(0x808080));
+        pixels.rotate_hue(1200);
+        for y in 0..4 {
+            for x in 0..4 {
+                if (x, y) != (3, 3) {
+                    assert_eq!(pixels.get(x, y), rgb(0x00ff00));
+                }
+            }
+        }
+        assert_eq!(pixels.get(3, 3), rgb(0x808080));
+    }
+
+    #[test]
+    fn test_premultiply() {
+        assert_eq!(premultiply([255, 128, 0, 255]), [255, 128, 0, 255]);
+        assert_eq!(premultiply([255, 128, 0, 128]), [128, 64, 0, 128]);
+        assert_eq!(premultiply([255, 128, 0, 0]), [0, 0, 0, 0]);
+        assert_eq!(unpremultiply([128, 64, 0, 128]), [255, 128, 0, 128]);
+        assert_eq!(unpremultiply([10, 20, 30, 0]), [0, 0, 0, 0]);
+
+        for &alpha in &[1u8, 17, 64, 128, 200, 254, 255] {
+            for &c in &[0u8, 1, 50, 127, 128, 200, 255] {
+                let back = unpremultiply(premultiply([c, c, c, alpha]));
+                let tolerance = (255 + alpha as i32 - 1) / alpha as i32;
+                assert!((back[0] as i32 - c as i32).abs() <= tolerance);
+                assert_eq!(back[3], alpha);
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_gamma() {
+        let mut pixels = Pixels::new(256, 1);
+        for x in 0..256 {
+            pixels.set(x, 0, RGB::new(x as u8, 255 - x as u8, 128));
+        }
+        let original = pixels.data.clone();
+        pixels.apply_gamma(1.0);
+        assert_eq!(pixels.data, original);
+
+        pixels.apply_gamma(2.2);
+        assert_eq!(pixels.get(0, 0), RGB::new(0, 255, 186));
+        assert_eq!(pixels.get(255, 0), RGB::new(255, 0, 186));
+        for x in 1..255 {
+            assert!(pixels.get(x, 0).red > x as u8);
+        }
+    }
+
+    #[test]
+    fn test_blend() {
+        let color = rgb(0xcc3366);
+        assert_eq!(blend_multiply(color, rgb(0xffffff)), color);
+        assert_eq!(blend_multiply(rgb(0xffffff), color), color);
+        assert_eq!(blend_multiply(color, rgb(0x000000)), rgb(0x000000));
+        assert_eq!(blend_multiply(rgb(0x808080), rgb(0x808080)), rgb(0x404040));
+
+        assert_eq!(blend_screen(color, rgb(0x000000)), color);
+        assert_eq!(blend_screen(rgb(0x000000), color), color);
+        assert_eq!(blend_screen(color, rgb(0xffffff)), rgb(0xffffff));
+        assert_eq!(blend_screen(rgb(0x808080), rgb(0x808080)), rgb(0xc0c0c0));
+
+        assert_eq!(blend_overlay(color, rgb(0x000000)), rgb(0x000000));
+        assert_eq!(blend_overlay(color, rgb(0xffffff)), rgb(0xffffff));
+        assert_eq!(blend_overlay(rgb(0x808080), rgb(0x4080c0)), rgb(0x4081c1));
+    }
+
+    #[test]
+    fn test_average() {
+        assert_eq!(
+            average(&[rgb(0xff0000), rgb(0x0000ff)]),
+            RGB::new(127, 0, 127)
+        );
+        assert_eq!(
+            average(&[rgb(0xff0000), rgb(0x00ff00), rgb(0x0000ff)]),
+            RGB::new(85, 85, 85)
+        );
+        assert_eq!(
+            average(&[rgb(0x010203), rgb(0x020304), rgb(0x020304)]),
+            RGB::new(2, 3, 4)
+        );
+        assert_eq!(average(&[rgb(0xffffff); 1000]), rgb(0xffffff));
+        assert_eq!(average(&[]), rgb(0x000000));
+    }
+
+    #[test]
+    fn test_checkerboard() {
+        let (a, b) = (rgb(0xcc3366), rgb(0x3366cc));
+        let pixels = Pixels::checkerboard(7, 5, 3, a, b);
+        assert_eq!((pixels.width, pixels.height), (7, 5));
+        assert_eq!(pixels.get(0, 0), a);
+        assert_eq!(pixels.get(2, 2), a);
+        assert_eq!(pixels.get(3, 0), b);
+        assert_eq!(pixels.get(0, 3), b);
+        assert_eq!(pixels.get(3, 3), a);
+        assert_eq!(pixels.get(6, 4), b);
+    }
+
+    #[test]
+    fn test_pixels_iter() {
+        let mut pixels = Pixels::new(3, 2);
+        pixels.set(2, 1, rgb(0xcc3366));
+        let collected: Vec<(u32, u32, RGB)> = pixels.pixels().collect();
+        assert_eq!(collected.len(), 6);
+        assert_eq!(collected[1], (1, 0, rgb(0xffffff)));
+        assert_eq!(collected[5], (2, 1, rgb(0xcc3366)));
+
+        let data: Vec<u8> = pixels
+            .pixels()
+            .flat_map(|(_, _, c)| <[u8; 3]>::from(c))
+            .collect();
+        assert_eq!(data, pixels.data);
+
+        for (x, y, c) in pixels.pixels_mut() {
+            if x == 0 {
+                *c = [x as u8, y as u8, 7];
+            }
+        }
+        assert_eq!(pixels.get(0, 1), RGB::new(0, 1, 7));
+        assert_eq!(pixels.get(1, 1), rgb(0xffffff));
+    }
+
+    #[test]
+    fn test_histogram() {
+        let mut pixels = Pixels::new(4, 3);
+        pixels.rect(0, 0, 1, 3, rgb(0xff0000));
+        let histogram = pixels.histogram();
+        assert_eq!(histogram.len(), 2);
+        assert_eq!(histogram[&rgb(0xff0000)], 3);
+        assert_eq!(histogram[&rgb(0xffffff)], 9);
+        assert_eq!(histogram.values().sum::<u32>(), 4 * 3);
+        assert_eq!(pixels.dominant_color(), Some(rgb(0xffffff)));
+
+        pixels.rect(1, 0, 1, 3, rgb(0xff0000));
+        pixels.rect(2, 0, 1, 3, rgb(0x00ff00));
+        pixels.rect(3, 0, 1, 3, rgb(0x00ff00));
+        assert_eq!(pixels.dominant_color(), Some(rgb(0x00ff00)));
+        assert_eq!(Pixels::new(0, 0).dominant_color(), None);
+    }
+
+    #[test]
+    fn test_get() {
+        let mut pixels = Pixels::new(3, 2);
+        pixels.set(2, 1, rgb(0xcc3366));
+        assert_eq!(pixels.get(2, 1), rgb(0xcc3366));
+        assert_eq!(pixels.get(0, 0), rgb(0xffffff));
+    }
+
+    #[test]
+    fn test_quantize() {
+        let mut pixels = Pixels::new(8, 4);
+        pixels.rect(0, 0, 4, 4, rgb(0xcc3333));
+        pixels.rect(4, 0, 4, 4, rgb(0x3333cc));
+        pixels.set(0, 0, rgb(0xcc3335));
+        pixels.set(7, 3, rgb(0x3331cc));
+
+        let colors = quantize(&pixels, 2);
+        assert_eq!(colors.len(), 2);
+        for expected in [rgb(0xcc3333), rgb(0x3333cc)].iter() {
+            assert!(colors.iter().any(|&c| delta_e_76(c, *expected) < 1.0));
+        }
+        assert_eq!(quantize(&pixels, 1).len(), 1);
+        assert_eq!(quantize(&Pixels::new(2, 2), 4), vec![rgb(0xffffff)]);
+    }
+
+    #[test]
+    fn test_dither_to_palette() {
+        let mut pixels = Pixels::new(8, 8);
+        pixels.rect(0, 0, 8, 8, rgb(0x808080));
+        pixels.dither_to_palette(&[rgb(0x000000), rgb(0xffffff)]);
+        let whites = pixels
+            .data
+            .chunks_exact(3)
+            .filter(|c| c == &[255, 255, 255])
+            .count();
+        let blacks = pixels
+            .data
+            .chunks_exact(3)
+            .filter(|c| c == &[0, 0, 0])
+            .count();
+        assert_eq!(whites + blacks, 64);
+        assert!(whites > 16 && blacks > 16);
+
+        let mut ramp = Pixels::new(16, 1);
+        for x in 0..16 {
+            ramp.set(x, 0, gray((x * 1000 / 15) as u16));
+        }
+        ramp.dither_to_palette(&[rgb(0x000000), rgb(0xffffff)]);
+        assert_eq!(ramp.get(0, 0), rgb(0x000000));
+        assert_eq!(ramp.get(15, 0), rgb(0xffffff));
+    }
+
+    #[test]
+    fn test_luminance() {
+        assert_eq!(luminance(rgb(0x000000)), 0);
+        assert_eq!(luminance(rgb(0xffffff)), 255);
+        assert_eq!(luminance(rgb(0x808080)), 128);
+        assert!(luminance(rgb(0x00ff00)) > luminance(rgb(0xff0000)));
+        assert!(luminance(rgb(0xff0000)) > luminance(rgb(0x0000ff)));
+    }
+
+    #[test]
+    fn test_to_grayscale() {
+        assert_eq!(
+            to_grayscale(rgb(0xffffff), LumaCoeffs::Rec709),
+            rgb(0xffffff)
+        );
+        assert_eq!(
+            to_grayscale(rgb(0x00ff00), LumaCoeffs::Rec601),
+            RGB::new(149, 149, 149)
+        );
+        assert_eq!(
+            to_grayscale(rgb(0x00ff00), LumaCoeffs::Rec709),
+            RGB::new(182, 182, 182)
+        );
+    }
+
+    #[test]
+    fn test_grayscale() {
+        let mut pixels = Pixels::new(12, 1);
+        for x in 0..12 {
+            pixels.set(x, 0, hue_to_rgb(x * 300));
+        }
+        pixels.grayscale(LumaCoeffs::Rec709);
+        for x in 0..12 {
+            let c = pixels.get(x, 0);
+            assert!(c.red == c.green && c.green == c.blue);
+        }
+        assert_eq!(pixels.get(0, 0), RGB::new(54, 54, 54));
+    }
+
+    #[test]
+    fn test_threshold() {
+        let mut pixels = Pixels::new(256, 1);
+        for x in 0..256 {
+            pixels.set(x, 0, RGB::new(x as u8, x as u8, x as u8));
+        }
+        pixels.threshold(100);
+        for x in 0..256 {
+            let expected = if x < 100 { 0x000000 } else { 0xffffff };
+            assert_eq!(pixels.get(x, 0), rgb(expected));
+        }
+
+        let mut pixels = Pixels::new(2, 1);
+        pixels.set(0, 0, rgb(0x0000ff));
+        pixels.set(1, 0, rgb(0x00ff00));
+        pixels.threshold(100);
+        assert_eq!(pixels.get(0, 0), rgb(0x000000));
+        assert_eq!(pixels.get(1, 0), rgb(0xffffff));
+    }
+
+    #[test]
+    fn test_box_blur() {
+        let mut pixels = Pixels::new(5, 5);
+        pixels.rect(0, 0, 5, 5, rgb(0x000000));
+        pixels.set(2, 2, rgb(0xffffff));
+        pixels.box_blur(0);
+        assert_eq!(pixels.get(2, 2), rgb(0xffffff));
+
+        pixels.box_blur(1);
+        for y in 0..5 {
+            for x in 0..5 {
+                let expected = if (1..=3).contains(&x) && (1..=3).contains(&y) {
+                    RGB::new(28, 28, 28)
+                } else {
+                    RGB::new(0, 0, 0)
+                };
+                assert_eq!(pixels.get(x, y), expected);
+            }
+        }
+
+        let mut pixels = Pixels::new(2, 1);
+        pixels.set(0, 0, rgb(0x000000));
+        pixels.box_blur(100);
+        assert_eq!(pixels.get(0, 0), RGB::new(127, 127, 127));
+        assert_eq!(pixels.get(1, 0), RGB::new(127, 127, 127));
+    }
+
+    #[test]
+    fn test_convolve() {
+        let mut pixels = Pixels::new(4, 3);
+        for x in 0..4 {
+            for y in 0..3 {
+                pixels.set(x, y, gray((x * 200 + y * 100) as u16));
+            }
+        }
+        let original = pixels.data.clone();
+        let identity = [[0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 0.0]];
+        pixels.convolve(&identity, 1.0);
+        assert_eq!(pixels.data, original);
+
+        let mut edge = Pixels::new(4, 1);
+        edge.set(0, 0, gray(400));
+        edge.set(1, 0, gray(400));
+        edge.set(2, 0, gray(600));
+        edge.set(3, 0, gray(600));
+        let sharpen = [[0.0, -1.0, 0.0], [-1.0, 5.0, -1.0], [0.0, -1.0, 0.0]];
+        edge.convolve(&sharpen, 1.0);
+        assert!(edge.get(2, 0).red - edge.get(1, 0).red > 51);
+        assert!(edge.get(1, 0).red < 102);
+        assert!(edge.get(2, 0).red > 153);
+
+        let mut blur = Pixels::new(3, 3);
+        blur.set(1, 1, rgb(0x000000));
+        blur.convolve(&[[1.0; 3]; 3], 9.0);
+        assert_eq!(blur.get(1, 1), RGB::new(227, 227, 227));
+    }
+
+    #[test]
+    fn test_sobel() {
+        let mut pixels = Pixels::new(6, 4);
+        pixels.rect(0, 0, 3, 4, rgb(0x000000));
+        let edges = pixels.sobel();
+        assert_eq!((edges.width, edges.height), (6, 4));
+        for y in 0..4 {
+            assert_eq!(edges.get(0, y), RGB::new(0, 0, 0));
+            assert_eq!(edges.get(1, y), RGB::new(0, 0, 0));
+            assert_eq!(edges.get(2, y), RGB::new(255, 255, 255));
+            assert_eq!(edges.get(3, y), RGB::new(255, 255, 255));
+            assert_eq!(edges.get(4, y), RGB::new(0, 0, 0));
+            assert_eq!(edges.get(5, y), RGB::new(0, 0, 0));
+        }
+    }
+
+    #[test]
+    fn test_pixelate() {
+        let mut pixels = Pixels::new(5, 3);
+        for y in 0..3 {
+            for x in 0..5 {
+                pixels.set(x, y, RGB::new((x * 50) as u8, (y * 100) as u8, 0));
+            }
+        }
+        let mut whole = Pixels::new(5, 3);
+        whole.blit(&pixels, 0, 0);
+        let colors: Vec<RGB> = (0..15).map(|i| pixels.get(i % 5, i / 5)).collect();
+        whole.pixelate(5);
+        for y in 0..3 {
+            for x in 0..5 {
+                assert_eq!(whole.get(x, y), average(&colors));
+            }
+        }
+
+        // blocks larger than the image cover it in a single tile
+        for &block in [70000, u32::MAX].iter() {
+            let mut huge = Pixels::new(5, 3);
+            huge.blit(&pixels, 0, 0);
+            huge.pixelate(block);
+            assert_eq!(huge.data, whole.data);
+        }
+
+        pixels.pixelate(2);
+        assert_eq!(pixels.get(0, 0), RGB::new(25, 50, 0));
+        assert_eq!(pixels.get(1, 1), RGB::new(25, 50, 0));
+        assert_eq!(pixels.get(4, 0), RGB::new(200, 50, 0));
+        assert_eq!(pixels.get(4, 2), RGB::new(200, 200, 0));
+        assert_eq!(pixels.get(2, 2), RGB::new(125, 200, 0));
+    }
+
+    #[test]
+    fn test_flood_fill() {
+        let mut pixels = Pixels::new(8, 8);
+        let black = rgb(0x000000);
+        for i in 1..=6 {
+            pixels.set(i, 1, black);
+            pixels.set(i, 6, black);
+            pixels.set(1, i, black);
+            pixels.set(6, i, black);
+        }
+        pixels.flood_fill(3, 3, rgb(0xff0000));
+        for y in 0..8 {
+            for x in 0..8 {
+                let expected = if (2..=5).contains(&x) && (2..=5).contains(&y) {
+                    rgb(0xff0000)
+                } else if (1..=6).contains(&x) && (1..=6).contains(&y) {
+                    black
+                } else {
+                    rgb(0xffffff)
+                };
+                assert_eq!(pixels.get(x, y), expected);
+            }
+        }
+
+        let mut large = Pixels::new(512, 512);
+        large.flood_fill(0, 0, black);
+        assert!(large.data.iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn test_flip() {
+        let mut pixels = Pixels::new(5, 3);
+        pixels.set(1, 0, rgb(0xcc3366));
+        pixels.set(4, 2, rgb(0x3366cc));
+        let original = pixels.data.clone();
+
+        pixels.flip_h();
+        assert_eq!(pixels.get(3, 0), rgb(0xcc3366));
+        assert_eq!(pixels.get(0, 2), rgb(0x3366cc));
+        assert_eq!(pixels.get(1, 0), rgb(0xffffff));
+        pixels.flip_h();
+        assert_eq!(pixels.data, original);
+
+        pixels.flip_v();
+        assert_eq!(pixels.get(1, 2), rgb(0xcc3366));
+        assert_eq!(pixels.get(4, 0), rgb(0x3366cc));
+        assert_eq!(pixels.get(1, 0), rgb(0xffffff));
+        pixels.flip_v();
+        assert_eq!(pixels.data, original);
+
+        for &(w, h) in [(0, 4), (4, 0), (0, 0)].iter() {
+            let mut empty = Pixels::new(w, h);
+            empty.flip_h();
+            empty.flip_v();
+            assert!(empty.data.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_resize_nearest() {
+        let mut pixels = Pixels::new(2, 2);
+        pixels.set(0, 0, rgb(0x000000));
+        pixels.set(1, 1, rgb(0x000000));
+
+        let large = pixels.resize_nearest(4, 4);
+        assert_eq!((large.width, large.height), (4, 4));
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if (x / 2 + y / 2) % 2 == 0 {
+                    0x000000
+                } else {
+                    0xffffff
+                };
+                assert_eq!(large.get(x, y), rgb(expected));
+            }
+        }
+
+        let small = large.resize_nearest(2, 2);
+        assert_eq!(small.data, pixels.data);
+        assert_eq!(pixels.resize_nearest(0, 3).data.len(), 0);
+    }
+
+    #[test]
+    fn test_resize_bilinear() {
+        let mut pixels = Pixels::new(1, 4);
+        for (y, value) in [0, 85, 170, 255].iter().enumerate() {
+            pixels.set(0, y as u32, RGB::new(*value, *value, *value));
+        }
+        let small = pixels.resize_bilinear(1, 2);
+        assert_eq!(small.get(0, 0), RGB::new(43, 43, 43));
+        assert_eq!(small.get(0, 1), RGB::new(213, 213, 213));
+
+        let mut pixels = Pixels::new(2, 1);
+        pixels.set(0, 0, rgb(0x000000));
+        let large = pixels.resize_bilinear(4, 1);
+        let row: Vec<u8> = (0..4).map(|x| large.get(x, 0)[0]).collect();
+        assert_eq!(row, vec![0, 64, 191, 255]);
+
+        assert_eq!(pixels.resize_bilinear(2, 1).data, pixels.data);
+    }
+
+    #[test]
+    fn test_resize_box_linear() {
+        let mut pixels = Pixels::new(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                if (x + y) % 2 == 0 {
+                    pixels.set(x, y, RGB::BLACK);
+                }
+            }
+        }
+        let gray = pixels.resize_box_linear(1, 1).get(0, 0);
+        assert_eq!(gray, RGB::new(188, 188, 188));
+        let half = pixels.resize_box_linear(2, 2);
+        assert!(half.pixels().all(|(_, _, c)| c == gray));
+        assert_eq!(pixels.resize_box_linear(4, 4).data, pixels.data);
+        assert_eq!(pixels.resize_box_linear(8, 8).get(1, 1), RGB::BLACK);
+        assert_eq!(pixels.resize_box_linear(3, 0).data.len(), 0);
+    }
+
+    #[test]
+    fn test_crop() {
+        let mut pixels = Pixels::new(6, 6);
+        for y in 0..6 {
+            for x in 0..6 {
+                pixels.set(x, y, RGB::new(x as u8, y as u8, 0));
+            }
+        }
+        let center = pixels.crop(2, 2, 2, 3);
+        assert_eq!((center.width, center.height), (2, 3));
+        assert_eq!(center.get(0, 0), RGB::new(2, 2, 0));
+        assert_eq!(center.get(1, 2), RGB::new(3, 4, 0));
+
+        let clamped = pixels.crop(4, 5, 10, 10);
+        assert_eq!((clamped.width, clamped.height), (2, 1));
+        assert_eq!(clamped.get(1, 0), RGB::new(5, 5, 0));
+        assert_eq!(pixels.crop(10, 0, 2, 2).data.len(), 0);
+    }
+
+    #[test]
+    fn test_blit() {
+        let mut sprite = Pixels::new(2, 2);
+        sprite.rect(0, 0, 2, 2, rgb(0xff0000));
+
+        let mut canvas = Pixels::new(4, 4);
+        canvas.blit(&sprite, 0, 0);
+        assert_eq!(canvas.get(0, 0), rgb(0xff0000));
+        assert_eq!(canvas.get(1, 1), rgb(0xff0000));
+        assert_eq!(canvas.get(2, 0), rgb(0xffffff));
+        assert_eq!(canvas.get(0, 2), rgb(0xffffff));
+
+        canvas.blit(&sprite, 3, 3);
+        assert_eq!(canvas.get(3, 3), rgb(0xff0000));
+        assert_eq!(canvas.get(2, 2), rgb(0xffffff));
+        canvas.blit(&sprite, 4, 0);
+    }
+
+    #[test]
+    fn test_color_error() {
+        let path = std::env::temp_dir()
+            .join("colors_missing_dir")
+            .join("out.png");
+        let pixels = Pixels::new(2, 2);
+        assert!(matches!(pixels.save_image(&path), Err(ColorError::Io(_))));
+        let err: ColorError = "#12".parse::<RGB>().unwrap_err().into();
+        assert!(matches!(err, ColorError::Parse(ref s) if s == "#12"));
+        assert_eq!(err.to_string(), "invalid color: \"#12\"");
+        assert!(matches!(pixels.save_ppm(&path), Err(ColorError::Io(_))));
+        assert!(matches!(pixels.save_bmp(&path), Err(ColorError::Io(_))));
+        assert!(matches!(save_gpl(&[], &path, "x"), Err(ColorError::Io(_))));
+        let err = ColorError::RowCount {
+            expected: 4,
+            found: 3,
+        };
+        assert_eq!(err.to_string(), "got 3 rows, expected 4");
+    }
+
+    #[test]
+    fn test_png_row_writer() {
+        let mut pixels = Pixels::new(5, 4);
+        for (x, y, c) in pixels.pixels_mut() {
+            *c = [x as u8 * 50, y as u8 * 60, 7];
+        }
+        let buffered = std::env::temp_dir().join("colors_test_rows_buffered.png");
+        let streamed = std::env::temp_dir().join("colors_test_rows_streamed.png");
+        pixels.save_image(&buffered).unwrap();
+        let mut writer = PngRowWriter::create(&streamed, 5, 4).unwrap();
+        assert!(matches!(
+            writer.write_row(&[0; 3]),
+            Err(ColorError::RowLength {
+                expected: 15,
+                found: 3
+            })
+        ));
+        for row in pixels.data.chunks(5 * 3) {
+            writer.write_row(row).unwrap();
+        }
+        assert!(matches!(
+            writer.write_row(&pixels.data[..15]),
+            Err(ColorError::RowCount {
+                expected: 4,
+                found: 5
+            })
+        ));
+        writer.finish().unwrap();
+
+        let decode = |path: &Path| {
+            let decoder = png::Decoder::new(File::open(path).unwrap());
+            let (info, mut reader) = decoder.read_info().unwrap();
+            let mut data = vec![0; info.buffer_size()];
+            reader.next_frame(&mut data).unwrap();
+            (info.width, info.height, info.color_type, data)
+        };
+        assert_eq!(decode(&streamed), decode(&buffered));
+        assert_eq!(decode(&streamed).3, pixels.data);
+        std::fs::remove_file(&buffered).unwrap();
+        std::fs::remove_file(&streamed).unwrap();
+
+        let path = std::env::temp_dir().join("colors_test_rows_short.png");
+        let mut writer = PngRowWriter::create(&path, 2, 2).unwrap();
+        writer.write_row(&[0; 6]).unwrap();
+        assert!(matches!(
+            writer.finish(),
+            Err(ColorError::RowCount {
+                expected: 2,
+                found: 1
+            })
+        ));
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
-    fn test_hwb_block_orange() {
-        assert_eq!(hwb_to_rgb((300, 0, 0)), rgb(0xff7f00));
-        assert_eq!(hwb_to_rgb((300, 200, 0)), rgb(0xff9933));
-        assert_eq!(hwb_to_rgb((300, 400, 0)), rgb(0xffb266));
-        assert_eq!(hwb_to_rgb((300, 600, 0)), rgb(0xffcc99));
-        assert_eq!(hwb_to_rgb((300, 800, 0)), rgb(0xffe5cc));
-        assert_eq!(hwb_to_rgb((300, 1000, 0)), rgb(0xffffff));
+    fn test_save_image_gray() {
+        let mut pixels = Pixels::new(3, 1);
+        pixels.set(0, 0, rgb(0x000000));
+        pixels.set(1, 0, rgb(0x808080));
+        pixels.set(2, 0, rgb(0x00ff00));
+        let path = std::env::temp_dir().join("colors_test_save_gray.png");
+        pixels.save_image_gray(&path).unwrap();
+
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let (info, mut reader) = decoder.read_info().unwrap();
+        assert_eq!(info.color_type, png::ColorType::Grayscale);
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        assert_eq!(data, vec![0, 128, 149]);
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
-    fn test_gray() {
-        assert_eq!(gray(500), [127, 127, 127]);
+    fn test_save_ppm() {
+        let mut pixels = Pixels::new(3, 2);
+        pixels.set(0, 0, rgb(0xcc3366));
+        let path = std::env::temp_dir().join("colors_test_save.ppm");
+        pixels.save_ppm(&path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let header = b"P6\n3 2\n255\n";
+        assert_eq!(&bytes[..header.len()], header);
+        assert_eq!(bytes.len(), header.len() + 3 * 2 * 3);
+        assert_eq!(&bytes[header.len()..header.len() + 3], &[0xcc, 0x33, 0x66]);
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
-    fn test_mix() {
-        assert_eq!(mix(500, [255, 0, 127], [0, 255, 127]), [127, 127, 127]);
+    fn test_save_bmp() {
+        let mut pixels = Pixels::new(3, 2);
+        pixels.set(0, 0, rgb(0x11aa22));
+        pixels.set(0, 1, rgb(0xcc3366));
+        let path = std::env::temp_dir().join("colors_test_save.bmp");
+        pixels.save_bmp(&path).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..2], b"BM");
+        let size = u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]);
+        assert_eq!(size, 54 + 2 * 12);
+        assert_eq!(bytes.len(), size as usize);
+        assert_eq!(&bytes[54..57], &[0x66, 0x33, 0xcc]);
+        assert_eq!(&bytes[63..66], &[0, 0, 0]);
+        assert_eq!(&bytes[66..69], &[0x22, 0xaa, 0x11]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_kelvin_to_rgb() {
+        let warm = kelvin_to_rgb(1000);
+        assert_eq!(warm.red, 255);
+        assert!(warm.green < 100);
+        assert_eq!(warm.blue, 0);
+
+        let neutral = kelvin_to_rgb(6500);
+        assert!(min(neutral) >= 240);
+
+        let cool = kelvin_to_rgb(20000);
+        assert_eq!(cool.blue, 255);
+        assert!(cool.red < cool.green && cool.green < cool.blue);
+
+        assert_eq!(kelvin_to_rgb(0), kelvin_to_rgb(1000));
+        assert_eq!(kelvin_to_rgb(100000), kelvin_to_rgb(40000));
+        for k in (1000..6600).step_by(100) {
+            assert!(kelvin_to_rgb(k).blue <= kelvin_to_rgb(k + 100).blue);
+        }
+    }
+
+    #[test]
+    fn test_wavelength_to_rgb() {
+        assert_eq!(wavelength_to_rgb(700.0), rgb(0xff0000));
+        let green = wavelength_to_rgb(550.0);
+        assert!(green.green == 255 && green.red < 200 && green.blue == 0);
+        let blue = wavelength_to_rgb(450.0);
+        assert!(blue.blue == 255 && blue.green < 100 && blue.red == 0);
+        assert_eq!(wavelength_to_rgb(300.0), rgb(0x000000));
+        assert_eq!(wavelength_to_rgb(800.0), rgb(0x000000));
+        assert!(wavelength_to_rgb(770.0).red < 255);
+    }
+
+    #[test]
+    fn test_readable_text_color() {
+        assert_eq!(readable_text_color(rgb(0x000080)), rgb(0xffffff));
+        assert_eq!(readable_text_color(rgb(0xffffe0)), rgb(0x000000));
+        assert_eq!(readable_text_color(rgb(0x000000)), rgb(0xffffff));
+        assert_eq!(readable_text_color(rgb(0xffffff)), rgb(0x000000));
+    }
+
+    #[test]
+    fn test_simulate_cvd() {
+        let red = rgb(0xff0000);
+        let green = rgb(0x00ff00);
+        let before = delta_e_76(red, green);
+        let after = delta_e_76(
+            simulate_cvd(red, Cvd::Protanopia),
+            simulate_cvd(green, Cvd::Protanopia),
+        );
+        assert!(after < before / 2.0);
+
+        for &kind in &[Cvd::Protanopia, Cvd::Deuteranopia, Cvd::Tritanopia] {
+            assert_eq!(simulate_cvd(rgb(0x000000), kind), rgb(0x000000));
+            assert!(delta_e_76(simulate_cvd(rgb(0x808080), kind), rgb(0x808080)) < 1.0);
+        }
+
+        let mut pixels = Pixels::new(2, 1);
+        pixels.set(0, 0, red);
+        pixels.simulate_cvd(Cvd::Deuteranopia);
+        assert_eq!(pixels.get(0, 0), simulate_cvd(red, Cvd::Deuteranopia));
+    }
+
+    #[test]
+    fn test_lab() {
+        let (l, a, b) = rgb_to_lab(rgb(0xffffff));
+        assert!((l - 100.0).abs() < 0.01);
+        assert!(a.abs() < 0.01);
+        assert!(b.abs() < 0.01);
+        let (l, a, b) = rgb_to_lab(rgb(0x000000));
+        assert!(l.abs() < 0.01);
+        assert!(a.abs() < 0.01);
+        assert!(b.abs() < 0.01);
+    }
+
+    #[test]
+    fn test_delta_e_76() {
+        assert_eq!(delta_e_76(rgb(0xcc3333), rgb(0xcc3333)), 0.0);
+        let near_black = rgb(0x101010);
+        assert!(delta_e_76(near_black, rgb(0x000000)) < delta_e_76(near_black, rgb(0xffffff)));
+    }
+
+    #[test]
+    fn test_lab_palette() {
+        let colors = [
+            rgb(0x000000),
+            rgb(0xff0000),
+            rgb(0x00ff00),
+            rgb(0x3366cc),
+            rgb(0xffffff),
+        ];
+        let palette = LabPalette::new(&colors);
+        for v in (0..0x1000000).step_by(0x10203) {
+            let target = rgb(v);
+            assert_eq!(palette.nearest(target), nearest_color(target, &colors));
+        }
+        assert_eq!(palette.nearest_index(rgb(0x3060d0)), Some(3));
+        assert_eq!(LabPalette::new(&[]).nearest(rgb(0x3060d0)), None);
+    }
+
+    #[test]
+    fn test_nearest_color() {
+        let palette = [rgb(0x000000), rgb(0xffffff), rgb(0xff0000)];
+        assert_eq!(nearest_color(rgb(0x101010), &palette), Some(rgb(0x000000)));
+        assert_eq!(nearest_color(rgb(0xee1010), &palette), Some(rgb(0xff0000)));
+        assert_eq!(nearest_color(rgb(0xffffff), &palette), Some(rgb(0xffffff)));
+        assert_eq!(nearest_color(rgb(0xffffff), &[]), None);
+    }
+
+    #[test]
+    fn test_nearest_name() {
+        assert_eq!(nearest_name(rgb(0xff0000)), "red");
+        assert_eq!(nearest_name(rgb(0xf80a05)), "red");
+        assert_eq!(nearest_name(rgb(0x00ffff)), "aqua");
+        assert_eq!(nearest_name(rgb(0x808080)), "gray");
+        assert_eq!(nearest_name(rgb(0x663399)), "rebeccapurple");
+        assert_eq!(nearest_name(rgb(0x010101)), "black");
+        for &(name, color) in CSS_COLORS.iter() {
+            assert_eq!(
+                CSS_COLORS.iter().find(|c| c.1 == color).unwrap().0,
+                nearest_name(color)
+            );
+            assert!(name.chars().all(|c| c.is_ascii_lowercase()));
+        }
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        assert_eq!(relative_luminance(rgb(0x000000)), 0.0);
+        assert!((relative_luminance(rgb(0xffffff)) - 1.0).abs() < 0.001);
+        assert!((contrast_ratio(rgb(0x000000), rgb(0xffffff)) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(rgb(0xffffff), rgb(0x000000)) - 21.0).abs() < 0.01);
+        assert!(passes_aa(rgb(0x000000), rgb(0xffffff)));
+        assert!(!passes_aa(rgb(0x777777), rgb(0x888888)));
+    }
+
+    #[test]
+    fn test_most_readable() {
+        let white = rgb(0xffffff);
+        let candidates = [rgb(0xffff66), rgb(0x1a237e), rgb(0x90caf9)];
+        assert_eq!(most_readable(white, &candidates), Some(rgb(0x1a237e)));
+        assert_eq!(
+            most_readable(rgb(0x000000), &candidates),
+            Some(rgb(0xffff66))
+        );
+        assert_eq!(most_readable(white, &[]), None);
+    }
+
+    #[test]
+    fn test_save_gpl() {
+        let colors = [rgb(0xff0000), rgb(0x0a0b0c), rgb(0xffffff)];
+        let path = std::env::temp_dir().join("colors_test_save.gpl");
+        save_gpl(&colors, &path, "Test").unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some("GIMP Palette"));
+        assert_eq!(lines.next(), Some("Name: Test"));
+        assert_eq!(lines.next(), Some("#"));
+        assert_eq!(lines.next(), Some("255   0   0\t#ff0000"));
+        let parsed: Vec<RGB> = text
+            .lines()
+            .skip(3)
+            .map(|line| {
+                let c: Vec<u8> = line
+                    .split_whitespace()
+                    .take(3)
+                    .map(|v| v.parse().unwrap())
+                    .collect();
+                RGB::new(c[0], c[1], c[2])
+            })
+            .collect();
+        assert_eq!(parsed, colors);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_to_css_variables() {
+        assert_eq!(
+            to_css_variables(&[rgb(0xff0000), rgb(0x0a0b0c)], "accent"),
+            ":root {\n  --accent-0: #ff0000;\n  --accent-1: #0a0b0c;\n}\n"
+        );
+        assert_eq!(to_css_variables(&[], "accent"), ":root {\n}\n");
+    }
+
+    fn ase_color(name: &str, model: &[u8; 4], values: &[f32]) -> Vec<u8> {
+        let mut data = vec![];
+        let name: Vec<u16> = name.encode_utf16().chain(Some(0)).collect();
+        data.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        for c in name {
+            data.extend_from_slice(&c.to_be_bytes());
+        }
+        data.extend_from_slice(model);
+        for v in values {
+            data.extend_from_slice(&v.to_be_bytes());
+        }
+        data.extend_from_slice(&2u16.to_be_bytes());
+
+        let mut block = vec![0x00, 0x01];
+        block.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        block.extend(data);
+        block
+    }
+
+    #[test]
+    fn test_parse_ase() {
+        let mut bytes = b"ASEF".to_vec();
+        bytes.extend_from_slice(&[0, 1, 0, 0]);
+        bytes.extend_from_slice(&6u32.to_be_bytes());
+        bytes.extend_from_slice(&[0xc0, 0x01, 0, 0, 0, 0]);
+        bytes.extend(ase_color("red", b"RGB ", &[1.0, 0.0, 0.0]));
+        bytes.extend(ase_color("teal", b"RGB ", &[0.0, 0.5, 0.5]));
+        bytes.extend(ase_color("cyan", b"CMYK", &[1.0, 0.0, 0.0, 0.0]));
+        bytes.extend(ase_color("lab", b"LAB ", &[0.5, 0.0, 0.0]));
+        bytes.extend(ase_color("gray", b"Gray", &[0.2]));
+
+        assert_eq!(
+            parse_ase(&bytes).unwrap(),
+            vec![rgb(0xff0000), rgb(0x008080), rgb(0x00ffff), rgb(0x333333)]
+        );
+        assert!(matches!(
+            parse_ase(&bytes[..bytes.len() - 1]),
+            Err(AseError::Truncated)
+        ));
+        assert!(matches!(
+            parse_ase(b"ASEX"),
+            Err(AseError::InvalidSignature)
+        ));
+
+        let path = std::env::temp_dir().join("colors_test_load.ase");
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(load_ase(&path).unwrap().len(), 4);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            load_ase("/nonexistent/x.ase"),
+            Err(AseError::Io(_))
+        ));
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn test_save_gif() {
+        let frames: Vec<Pixels> = [rgb(0xff0000), rgb(0x00ff00), rgb(0x0000ff)]
+            .iter()
+            .map(|&c| {
+                let mut frame = Pixels::new(4, 4);
+                frame.rect(0, 0, 2, 2, c);
+                frame
+            })
+            .collect();
+        let path = std::env::temp_dir().join("colors_test_save.gif");
+        save_gif(&frames, &path, 10).unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(File::open(&path).unwrap()).unwrap();
+        let mut count = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!((frame.width, frame.height), (4, 4));
+            assert_eq!(frame.delay, 10);
+            let expected: [u8; 3] = frames[count].get(0, 0).into();
+            assert_eq!(&frame.buffer[..3], &expected);
+            count += 1;
+        }
+        assert_eq!(count, 3);
+        std::fs::remove_file(&path).unwrap();
+
+        let mismatched = [Pixels::new(4, 4), Pixels::new(2, 2)];
+        assert!(matches!(
+            save_gif(&mismatched, &path, 10),
+            Err(ColorError::SizeMismatch {
+                expected: (4, 4),
+                found: (2, 2)
+            })
+        ));
+        assert!(matches!(
+            save_gif(&[], &path, 10),
+            Err(ColorError::NoFrames)
+        ));
+        assert!(matches!(
+            save_gif(&[Pixels::new(70000, 1)], &path, 10),
+            Err(ColorError::TooLarge {
+                width: 70000,
+                height: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_color_wheel() {
+        let wheel = color_wheel(64);
+        assert_eq!((wheel.width, wheel.height), (64, 64));
+        let center = wheel.get(32, 32);
+        assert!(min(center) >= 240);
+
+        let (hue, w, b) = rgb_to_hwb(wheel.get(48, 32));
+        assert!(!(100..=3500).contains(&hue));
+        assert!((400..600).contains(&w));
+        assert_eq!(b, 0);
+
+        let (hue, _, _) = rgb_to_hwb(wheel.get(32, 1));
+        assert!((hue as i32 - 900).abs() < 100);
+        assert_eq!(wheel.get(0, 0), rgb(0xffffff));
+        assert!(rgb_to_hwb(wheel.get(63, 32)).1 < 50);
+    }
+
+    #[test]
+    fn test_line() {
+        let mut pixels = Pixels::new(8, 8);
+        let black = RGB::new(0, 0, 0);
+        pixels.line(0, 0, 7, 7, black);
+        for i in 0..8 {
+            assert_eq!(pixels.get(i, i), black);
+        }
+        assert_eq!(pixels.get(1, 0), rgb(0xffffff));
+        // endpoints outside the canvas are clipped
+        pixels.line(-5, 3, 20, 3, black);
+        assert_eq!(pixels.get(0, 3), black);
+        assert_eq!(pixels.get(7, 3), black);
+    }
+
+    #[test]
+    fn test_line_thick() {
+        let black = RGB::new(0, 0, 0);
+        let mut pixels = Pixels::new(10, 10);
+        pixels.line_thick(1, 4, 8, 4, 3, black);
+        for x in 1..=8 {
+            for y in 3..=5 {
+                assert_eq!(pixels.get(x, y), black);
+            }
+            assert_eq!(pixels.get(x, 2), rgb(0xffffff));
+            assert_eq!(pixels.get(x, 6), rgb(0xffffff));
+        }
+        let mut thin = Pixels::new(10, 10);
+        let mut thick = Pixels::new(10, 10);
+        thin.line(0, 9, 9, 2, black);
+        thick.line_thick(0, 9, 9, 2, 1, black);
+        assert_eq!(thin.data, thick.data);
+        let mut empty = Pixels::new(10, 10);
+        empty.line_thick(0, 9, 9, 2, 0, black);
+        assert!(empty.pixels().all(|(_, _, c)| c == rgb(0xffffff)));
+    }
+
+    #[test]
+    fn test_fill_polygon() {
+        let black = RGB::new(0, 0, 0);
+        let mut pixels = Pixels::new(10, 10);
+        pixels.fill_polygon(&[(0, 0), (10, 0), (0, 10)], black);
+        assert_eq!(pixels.get(2, 2), black);
+        assert_eq!(pixels.get(8, 8), rgb(0xffffff));
+        // concave and partly off-canvas
+        let mut pixels = Pixels::new(10, 10);
+        let shape = [(-5, 0), (15, 0), (15, 10), (5, 4), (-5, 10)];
+        pixels.fill_polygon(&shape, black);
+        assert_eq!(pixels.get(0, 1), black);
+        assert_eq!(pixels.get(0, 5), black);
+        assert_eq!(pixels.get(5, 8), rgb(0xffffff));
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let black = RGB::new(0, 0, 0);
+        let white = rgb(0xffffff);
+        let mut pixels = Pixels::new(20, 10);
+        pixels.draw_text(1, 1, "A", black);
+        // apex, both legs and the crossbar
+        assert_eq!(pixels.get(3, 1), black);
+        assert_eq!(pixels.get(1, 7), black);
+        assert_eq!(pixels.get(5, 7), black);
+        assert_eq!(pixels.get(3, 5), black);
+        assert_eq!(pixels.get(1, 1), white);
+        assert_eq!(pixels.get(3, 7), white);
+        // unsupported characters render as a box
+        pixels.draw_text(7, 1, "é", black);
+        assert_eq!(pixels.get(7, 1), black);
+        assert_eq!(pixels.get(11, 7), black);
+        assert_eq!(pixels.get(9, 4), white);
+        // text running off the canvas is clipped
+        pixels.draw_text(17, 1, "AA", black);
+        let before = pixels.data.clone();
+        pixels.draw_text(u32::MAX - 3, 1, "AAAA", black);
+        pixels.draw_text(1, u32::MAX, "A", black);
+        assert_eq!(pixels.data, before);
+        // only the first box of a very long string fits
+        pixels.draw_text(19, 2, &"é".repeat(1000), black);
+        assert!((2..9).all(|y| pixels.get(19, y) == black));
+    }
+
+    #[test]
+    fn test_pixels_tint() {
+        let mut ramp = Pixels::new(16, 1);
+        for x in 0..16 {
+            ramp.set(x, 0, gray(x as u16 * 60));
+        }
+        let original = ramp.data.clone();
+        ramp.tint(RGB::BLUE, 0);
+        assert_eq!(ramp.data, original);
+        ramp.tint(RGB::BLUE, 400);
+        for x in 0..16 {
+            assert!(ramp.get(x, 0).blue > original[x as usize * 3 + 2]);
+        }
+        ramp.tint(RGB::BLUE, 1000);
+        assert!(ramp.pixels().all(|(_, _, c)| c == RGB::BLUE));
+
+        // strengths past 1000 saturate instead of wrapping
+        let mut pixels = Pixels::new(4, 1);
+        pixels.rect(0, 0, 2, 1, RGB::BLACK);
+        pixels.tint(RGB::RED, 2000);
+        assert!(pixels.pixels().all(|(_, _, c)| c == RGB::RED));
+    }
+
+    #[test]
+    fn test_ellipse() {
+        let black = RGB::BLACK;
+        let mut pixels = Pixels::new(21, 11);
+        pixels.ellipse(10, 5, 8, 3, black);
+        for &(x, y) in [(2, 5), (18, 5), (10, 2), (10, 8)].iter() {
+            assert_eq!(pixels.get(x, y), black);
+        }
+        assert_eq!(pixels.get(10, 5), RGB::WHITE);
+        assert_eq!(pixels.get(1, 5), RGB::WHITE);
+        assert_eq!(pixels.get(10, 1), RGB::WHITE);
+        let mut filled = Pixels::new(21, 11);
+        filled.filled_ellipse(10, 5, 8, 3, black);
+        for x in 2..=18 {
+            assert_eq!(filled.get(x, 5), black);
+        }
+        assert_eq!(filled.get(10, 3), black);
+        assert_eq!(filled.get(2, 2), RGB::WHITE);
+        // every outline pixel is covered by the filled shape
+        assert!(pixels
+            .pixels()
+            .all(|(x, y, c)| c == RGB::WHITE || filled.get(x, y) == black));
+        // clipped at the edges
+        filled.filled_ellipse(0, 0, 30, 30, black);
+        assert!(filled.pixels().all(|(_, _, c)| c == black));
+    }
+
+    #[test]
+    fn test_arc() {
+        let black = RGB::BLACK;
+        let mut pixels = Pixels::new(21, 21);
+        pixels.arc(10, 10, 8, 0, 90, black);
+        assert_eq!(pixels.get(18, 10), black);
+        assert_eq!(pixels.get(10, 2), black);
+        assert!(pixels
+            .pixels()
+            .all(|(x, y, c)| c == RGB::WHITE || (x >= 10 && y <= 10)));
+        assert_eq!(pixels.get(2, 10), RGB::WHITE);
+        // wrapping through 0 covers the right half
+        let mut pixels = Pixels::new(21, 21);
+        pixels.arc(10, 10, 8, 270, 90, black);
+        assert_eq!(pixels.get(10, 18), black);
+        assert!(pixels.pixels().all(|(x, _, c)| c == RGB::WHITE || x >= 10));
+        // a full turn matches the circle
+        let mut arc = Pixels::new(21, 21);
+        let mut circle = Pixels::new(21, 21);
+        arc.arc(10, 10, 8, 0, 360, black);
+        circle.circle(10, 10, 8, black);
+        assert_eq!(arc.data, circle.data);
+        // start angles near u32::MAX don't overflow, this is 245 to 255 degrees
+        let mut arc = Pixels::new(21, 21);
+        arc.arc(10, 10, 8, u32::MAX - 10, u32::MAX, black);
+        assert_eq!(arc.get(2, 10), RGB::WHITE);
+        assert!(arc.pixels().any(|(_, _, c)| c == black));
+    }
+
+    #[test]
+    fn test_polyline() {
+        let black = RGB::BLACK;
+        let points = [(1, 1), (8, 1), (8, 8)];
+        let mut segments = Pixels::new(10, 10);
+        segments.line(1, 1, 8, 1, black);
+        segments.line(8, 1, 8, 8, black);
+        let mut open = Pixels::new(10, 10);
+        open.polyline(&points, false, black);
+        assert_eq!(open.data, segments.data);
+        assert_eq!(open.get(4, 4), RGB::WHITE);
+        segments.line(8, 8, 1, 1, black);
+        let mut closed = Pixels::new(10, 10);
+        closed.polyline(&points, true, black);
+        assert_eq!(closed.data, segments.data);
+        assert_eq!(closed.get(4, 4), black);
+        let mut single = Pixels::new(10, 10);
+        single.polyline(&[(3, 3)], true, black);
+        single.polyline(&[], true, black);
+        assert_eq!(single.pixels().filter(|p| p.2 == black).count(), 1);
+    }
+
+    #[test]
+    fn test_sharpen() {
+        let mut pixels = Pixels::new(6, 3);
+        for y in 0..3 {
+            for (x, v) in [60u8, 60, 100, 150, 190, 190].iter().enumerate() {
+                pixels.set(x as u32, y, RGB::new(*v, *v, *v));
+            }
+        }
+        let original = pixels.data.clone();
+        pixels.sharpen(0.0);
+        assert_eq!(pixels.data, original);
+        let before = pixels.get(3, 1).red - pixels.get(2, 1).red;
+        pixels.sharpen(1.0);
+        let after = pixels.get(3, 1).red - pixels.get(2, 1).red;
+        assert!(after > before);
+    }
+
+    #[test]
+    fn test_median_filter() {
+        let mut pixels = Pixels::new(5, 5);
+        pixels.rect(0, 0, 5, 5, RGB::BLACK);
+        pixels.set(2, 2, RGB::WHITE);
+        pixels.set(0, 0, RGB::WHITE);
+        pixels.median_filter(1);
+        assert!(pixels.pixels().all(|(_, _, c)| c == RGB::BLACK));
+        // edges survive, unlike with box_blur
+        let mut edge = Pixels::new(6, 3);
+        edge.rect(0, 0, 3, 3, RGB::BLACK);
+        let original = edge.data.clone();
+        edge.median_filter(1);
+        assert_eq!(edge.data, original);
+    }
+
+    #[test]
+    fn test_rect_clipped() {
+        let mut pixels = Pixels::new(8, 6);
+        pixels.rect(6, 4, 100, 100, RGB::BLACK);
+        for (x, y, c) in pixels.pixels() {
+            assert_eq!(c == RGB::BLACK, x >= 6 && y >= 4);
+        }
+        pixels.rect(20, 20, 5, 5, RGB::BLACK);
+        pixels.rect(0, 0, u32::MAX, 0, RGB::BLACK);
+        assert_eq!(pixels.get(0, 0), RGB::WHITE);
+        pixels.rect(1, 1, 2, 3, RGB::RED);
+        assert_eq!(pixels.pixels().filter(|p| p.2 == RGB::RED).count(), 6);
+    }
+
+    #[test]
+    fn test_to_rgba() {
+        let mut pixels = Pixels::new(3, 2);
+        pixels.set(0, 0, rgb(0xcc3366));
+        pixels.set(2, 1, rgb(0x123456));
+        let rgba = pixels.to_rgba(128);
+        assert_eq!(rgba.data.len(), 3 * 2 * 4);
+        assert_eq!(rgba.get(0, 0), [0xcc, 0x33, 0x66, 128]);
+        assert_eq!(rgba.get(2, 1), [0x12, 0x34, 0x56, 128]);
+        let back = rgba.to_rgb();
+        assert_eq!((back.width, back.height), (3, 2));
+        assert_eq!(back.data, pixels.data);
+        let path = std::env::temp_dir().join("colors_test_save_rgba.png");
+        rgba.save_image(&path).unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(b"\x89PNG"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_channels() {
+        let mut pixels = Pixels::new(3, 2);
+        pixels.set(0, 0, rgb(0xcc3366));
+        pixels.set(2, 1, rgb(0x123456));
+        let red = pixels.channel(Channel::Red);
+        assert_eq!(red.get(0, 0), rgb(0xcccccc));
+        assert_eq!(red.get(2, 1), rgb(0x121212));
+        assert_eq!(red.get(1, 0), rgb(0xffffff));
+        let green = pixels.channel(Channel::Green);
+        let blue = pixels.channel(Channel::Blue);
+        assert_eq!(blue.get(0, 0), rgb(0x666666));
+        let back = Pixels::from_channels(&red, &green, &blue).unwrap();
+        assert_eq!((back.width, back.height), (3, 2));
+        assert_eq!(back.data, pixels.data);
+        assert_eq!(
+            Pixels::from_channels(&pixels, &pixels, &pixels)
+                .unwrap()
+                .data,
+            pixels.data
+        );
+        assert!(matches!(
+            Pixels::from_channels(&red, &green, &Pixels::new(2, 3)),
+            Err(ColorError::SizeMismatch {
+                expected: (3, 2),
+                found: (2, 3)
+            })
+        ));
+    }
+
+    #[test]
+    fn test_rgb_shift() {
+        let mut pixels = Pixels::new(7, 3);
+        pixels.rect(0, 0, 7, 3, RGB::BLACK);
+        pixels.set(3, 1, RGB::WHITE);
+        pixels.rgb_shift(2, 0, -2, 0);
+        assert_eq!(pixels.get(3, 1), RGB::GREEN);
+        assert_eq!(pixels.get(5, 1), RGB::RED);
+        assert_eq!(pixels.get(1, 1), RGB::BLUE);
+        assert_eq!(pixels.get(3, 0), RGB::BLACK);
+
+        // samples past the edge repeat the border
+        let mut pixels = Pixels::new(3, 1);
+        pixels.set(0, 0, rgb(0x102030));
+        pixels.rgb_shift(5, 0, 0, 0);
+        assert_eq!(pixels.get(2, 0), rgb(0x10ffff));
+        assert_eq!(pixels.get(0, 0), rgb(0x102030));
+        pixels.rgb_shift(i32::MIN, i32::MAX, i32::MAX, i32::MIN);
+        assert_eq!(pixels.get(0, 0), rgb(0x102030));
+        assert_eq!(pixels.get(2, 0), rgb(0x10ff30));
+    }
+
+    #[test]
+    fn test_vignette() {
+        let mut pixels = Pixels::new(9, 9);
+        pixels.vignette(0.0);
+        assert!(pixels.pixels().all(|(_, _, c)| c == RGB::WHITE));
+        pixels.vignette(0.5);
+        assert_eq!(pixels.get(4, 4), RGB::WHITE);
+        let corner = pixels.get(0, 0);
+        // the corner pixel center sits just inside the corner itself
+        assert_eq!(corner, RGB::new(154, 154, 154));
+        assert_eq!(pixels.get(8, 8), corner);
+        assert_eq!(pixels.get(8, 0), corner);
+        assert!(pixels.get(4, 0).red > corner.red);
+        assert!(pixels.get(2, 2).red > pixels.get(1, 1).red);
+    }
+
+    #[test]
+    fn test_adjust_saturation() {
+        let mut pixels = Pixels::new(4, 2);
+        let colors = [0xcc3366, 0x336699, 0x808080, 0x10f020, 0x000000, 0xff8000];
+        for (i, &color) in colors.iter().enumerate() {
+            pixels.set(i as u32 % 4, i as u32 / 4, rgb(color));
+        }
+        let original = pixels.data.clone();
+
+        let mut gray = Pixels::new(4, 2);
+        gray.data.copy_from_slice(&original);
+        gray.adjust_saturation(0.0);
+        let mut expected = Pixels::new(4, 2);
+        expected.data.copy_from_slice(&original);
+        expected.grayscale(LumaCoeffs::Rec601);
+        assert_eq!(gray.data, expected.data);
+
+        pixels.adjust_saturation(1.0);
+        assert_eq!(pixels.data, original);
+
+        let spread = |c: RGB| max(c) - min(c);
+        let mut boosted = Pixels::new(4, 2);
+        boosted.data.copy_from_slice(&original);
+        boosted.adjust_saturation(2.0);
+        assert!(spread(boosted.get(0, 0)) > spread(pixels.get(0, 0)));
+        assert!(spread(boosted.get(1, 0)) > spread(pixels.get(1, 0)));
+        assert_eq!(boosted.get(2, 0), rgb(0x808080));
+        assert_eq!(boosted.get(3, 0).green, 255);
+    }
+
+    #[test]
+    fn test_rgba_over() {
+        let background = Pixels::new(2, 2).to_rgba(255);
+        let mut layer = PixelsRgba::new(2, 2);
+        for y in 0..2 {
+            for x in 0..2 {
+                layer.set(x, y, [255, 0, 0, 128]);
+            }
+        }
+        layer.set(1, 1, [0, 255, 0, 0]);
+        let out = layer.over(&background).unwrap();
+        assert_eq!(out.get(0, 0), [255, 127, 127, 255]);
+        assert_eq!(out.get(1, 1), [255, 255, 255, 255]);
+
+        // a transparent layer below keeps the color of the one on top
+        let mut clear = PixelsRgba::new(2, 2);
+        clear.set(0, 0, [0, 0, 255, 0]);
+        assert_eq!(layer.over(&clear).unwrap().get(0, 0)[..3], [255, 0, 0]);
+        let mut empty = PixelsRgba::new(1, 1);
+        empty.set(0, 0, [0, 0, 0, 0]);
+        let mut top = PixelsRgba::new(1, 1);
+        top.set(0, 0, [200, 100, 50, 64]);
+        assert_eq!(top.over(&empty).unwrap().get(0, 0), [200, 100, 50, 64]);
+        assert_eq!(empty.over(&empty).unwrap().get(0, 0), [0, 0, 0, 0]);
+
+        assert!(matches!(
+            layer.over(&PixelsRgba::new(3, 2)),
+            Err(ColorError::SizeMismatch {
+                expected: (2, 2),
+                found: (3, 2)
+            })
+        ));
+    }
+
+    #[test]
+    fn test_rgba_premultiplied() {
+        let mut pixels = PixelsRgba::new(2, 1);
+        pixels.set(1, 0, [255, 0, 0, 0]);
+        assert!(!pixels.is_premultiplied());
+        pixels.premultiply();
+        assert!(pixels.is_premultiplied());
+        assert_eq!(pixels.get(1, 0), [0, 0, 0, 0]);
+        pixels.premultiply();
+        assert_eq!(pixels.get(0, 0), [255, 255, 255, 255]);
+        pixels.unpremultiply();
+        assert!(!pixels.is_premultiplied());
+
+        // the transparent red pixel must not tint the white one
+        for &premultiplied in [false, true].iter() {
+            let mut pixels = PixelsRgba::new(2, 1);
+            pixels.set(1, 0, [255, 0, 0, 0]);
+            if premultiplied {
+                pixels.premultiply();
+            }
+            let small = pixels.resize_bilinear(1, 1);
+            assert_eq!(small.is_premultiplied(), premultiplied);
+            assert_eq!(small.to_rgb().get(0, 0), RGB::WHITE);
+            assert_eq!(small.get(0, 0)[3], 128);
+            pixels.box_blur(1);
+            assert_eq!(pixels.is_premultiplied(), premultiplied);
+            assert_eq!(pixels.to_rgb().get(1, 0), RGB::WHITE);
+            assert_eq!(pixels.get(1, 0)[3], 127);
+        }
+        assert_eq!(
+            PixelsRgba::new(2, 2).resize_bilinear(4, 4).get(3, 3),
+            [255; 4]
+        );
+
+        // over gives the same colors for either storage
+        let background = Pixels::new(1, 1).to_rgba(255);
+        let mut layer = PixelsRgba::new(1, 1);
+        layer.set(0, 0, [200, 100, 50, 128]);
+        let straight = layer.over(&background).unwrap();
+        layer.premultiply();
+        let premultiplied = layer.over(&background).unwrap();
+        assert!(premultiplied.is_premultiplied());
+        assert_eq!(premultiplied.to_rgb().data, straight.to_rgb().data);
+        assert_eq!(straight.get(0, 0), [227, 177, 152, 255]);
+    }
+
+    #[test]
+    fn test_pixels16() {
+        let (a, b) = (RGB::BLACK, rgb(0x202020));
+        let mut wide = Pixels16::new(1024, 1);
+        let mut narrow = Pixels::new(1024, 1);
+        for x in 0..1024 {
+            let t = x as f32 / 1023.0;
+            wide.set(x, 0, mix_f16(t, a, b));
+            narrow.set(x, 0, mix_f(t, a, b));
+        }
+        let mut steps16: Vec<u16> = wide.data.clone();
+        steps16.dedup();
+        let mut steps8: Vec<u8> = narrow.data.clone();
+        steps8.dedup();
+        assert!(steps16.len() > 10 * steps8.len());
+        assert_eq!(wide.get(1023, 0), [0x2020; 3]);
+        assert_eq!(wide.to_rgb().data, narrow.data);
+        assert_eq!(narrow.to_16().to_rgb().data, narrow.data);
+
+        let mut pixels = Pixels16::new(2, 1);
+        pixels.set(1, 0, [0x1234, 0x0001, 0xff00]);
+        let path = std::env::temp_dir().join("colors_test_save_16.png");
+        pixels.save_image(&path).unwrap();
+        let mut decoder = png::Decoder::new(File::open(&path).unwrap());
+        decoder.set_transformations(png::Transformations::IDENTITY);
+        let (info, mut reader) = decoder.read_info().unwrap();
+        assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+        let mut data = vec![0; info.buffer_size()];
+        reader.next_frame(&mut data).unwrap();
+        assert_eq!(data[6..], [0x12, 0x34, 0x00, 0x01, 0xff, 0x00]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_parallel_matches_serial() {
+        let mut pixels = Pixels::new(37, 23);
+        for y in 0..23 {
+            for x in 0..37 {
+                pixels.set(x, y, RGB::new((x * 7) as u8, (y * 11) as u8, (x * y) as u8));
+            }
+        }
+        let kernel = [[1.0, 2.0, 1.0], [0.0, 0.0, 0.0], [-1.0, -2.0, -1.0]];
+        let mut expected = pixels.data.clone();
+        for (y, row) in expected.chunks_mut(37 * 3).enumerate() {
+            pixels.convolve_row(&kernel, 1.0, y as i32, row);
+        }
+        let mut parallel = Pixels {
+            width: 37,
+            height: 23,
+            data: pixels.data.clone(),
+        };
+        parallel.convolve(&kernel, 1.0);
+        assert_eq!(parallel.data, expected);
+
+        let serial: Vec<u8> = pixels
+            .pixels()
+            .flat_map(|(_, _, c)| <[u8; 3]>::from(to_grayscale(c, LumaCoeffs::Rec709)))
+            .collect();
+        pixels.grayscale(LumaCoeffs::Rec709);
+        assert_eq!(pixels.data, serial);
+        let serial: Vec<u8> = pixels.data.iter().map(|c| 255 - c).collect();
+        pixels.invert();
+        assert_eq!(pixels.data, serial);
+        let serial: Vec<u8> = pixels
+            .data
+            .iter()
+            .map(|&c| f32_to_u8_clamped((c as f32 / 255.0).powf(1.0 / 2.2) * 255.0))
+            .collect();
+        pixels.apply_gamma(2.2);
+        assert_eq!(pixels.data, serial);
+    }
+
+    #[test]
+    fn test_grid() {
+        let black = RGB::new(0, 0, 0);
+        let mut pixels = Pixels::new(8, 3);
+        pixels.grid(4, black);
+        for y in 0..3 {
+            assert_eq!(pixels.get(0, y), black);
+            assert_eq!(pixels.get(4, y), black);
+        }
+        assert_eq!(pixels.get(2, 0), black);
+        assert_eq!(pixels.get(2, 1), rgb(0xffffff));
+        assert_eq!(pixels.get(2, 2), rgb(0xffffff));
+
+        let mut pixels = Pixels::new(8, 3);
+        pixels.grid_offset(4, 6, 1, black);
+        assert_eq!(pixels.get(2, 0), black);
+        assert_eq!(pixels.get(6, 2), black);
+        assert_eq!(pixels.get(3, 1), black);
+        assert_eq!(pixels.get(0, 0), rgb(0xffffff));
+        assert_eq!(pixels.get(4, 2), rgb(0xffffff));
+
+        let mut pixels = Pixels::new(8, 3);
+        pixels.grid(0, black);
+        assert!(pixels.pixels().all(|p| p.2 == rgb(0xffffff)));
+    }
+
+    #[test]
+    fn test_rect_outline() {
+        let mut pixels = Pixels::new(8, 8);
+        let black = RGB::new(0, 0, 0);
+        pixels.rect_outline(1, 1, 5, 4, black);
+        for &(x, y) in [(1, 1), (5, 1), (1, 4), (5, 4), (3, 1), (1, 2), (5, 3)].iter() {
+            assert_eq!(pixels.get(x, y), black);
+        }
+        for x in 2..5 {
+            for y in 2..4 {
+                assert_eq!(pixels.get(x, y), rgb(0xffffff));
+            }
+        }
+        assert_eq!(pixels.get(6, 1), rgb(0xffffff));
+        assert_eq!(pixels.get(1, 5), rgb(0xffffff));
+        // partly off-canvas outlines are clipped
+        pixels.rect_outline(6, 6, 10, 10, black);
+        assert_eq!(pixels.get(7, 6), black);
+        assert_eq!(pixels.get(7, 7), rgb(0xffffff));
+
+        let mut pixels = Pixels::new(8, 8);
+        pixels.rect_outline(2, 2, u32::MAX, 3, black);
+        assert_eq!(pixels.get(0, 2), rgb(0xffffff));
+        assert_eq!(pixels.get(2, 3), black);
+        assert_eq!(pixels.get(7, 2), black);
+        assert_eq!(pixels.get(7, 3), rgb(0xffffff));
+        pixels.rect_outline(1, 1, 3, u32::MAX, black);
+        assert_eq!(pixels.get(1, 7), black);
+        assert_eq!(pixels.get(3, 7), black);
+        assert_eq!(pixels.get(2, 7), rgb(0xffffff));
+        pixels.rect_outline(u32::MAX, 0, u32::MAX, u32::MAX, black);
+        assert_eq!(pixels.get(0, 0), rgb(0xffffff));
+    }
+
+    #[test]
+    fn test_harmonies() {
+        assert_eq!(split_complementary((0, 0, 0)), [(1500, 0, 0), (2100, 0, 0)]);
+        assert_eq!(
+            tetradic((0, 100, 200)),
+            [(900, 100, 200), (1800, 100, 200), (2700, 100, 200)]
+        );
+        assert_eq!(
+            split_complementary((3000, 50, 0)),
+            [(900, 50, 0), (1500, 50, 0)]
+        );
+        assert_eq!(tetradic((3500, 0, 0))[2], (2600, 0, 0));
+    }
+
+    #[test]
+    fn test_monochromatic() {
+        let shades = monochromatic((1200, 0, 0), 4);
+        assert_eq!(
+            shades,
+            vec![
+                (1200, 0, 600),
+                (1200, 0, 200),
+                (1200, 200, 0),
+                (1200, 600, 0)
+            ]
+        );
+        assert_eq!(monochromatic((1200, 0, 0), 3)[1], (1200, 0, 0));
+        let first = hwb_to_rgb(shades[0]);
+        let last = hwb_to_rgb(shades[3]);
+        assert!(luminance(first) < luminance(last));
+        assert!(monochromatic((0, 0, 0), 0).is_empty());
+    }
+
+    #[test]
+    fn test_f32_to_u8_clamped() {
+        assert_eq!(f32_to_u8_clamped(255.3), 255);
+        assert_eq!(f32_to_u8_clamped(-51.0), 0);
+        assert_eq!(f32_to_u8_clamped(127.5), 128);
+        assert_eq!(f32_to_u8_clamped(254.4), 254);
+        assert_eq!(f32_to_u8_clamped(f32::INFINITY), 255);
+        assert_eq!(f32_to_u8_clamped(f32::NEG_INFINITY), 0);
+    }
+
+    #[test]
+    fn test_is_dark() {
+        assert!(is_dark(RGB::BLACK));
+        assert!(is_dark(rgb(0x000080)));
+        assert!(is_light(RGB::WHITE));
+        assert!(is_light(rgb(0xffffe0)));
+        for &color in [RGB::BLACK, RGB::WHITE, RGB::RED, RGB::GREEN].iter() {
+            assert_ne!(is_dark(color), is_light(color));
+        }
+    }
+
+    #[test]
+    fn test_tint_shade_tone() {
+        let color = rgb(0xcc3366);
+        assert_eq!(tint(color, 1000), RGB::WHITE);
+        assert_eq!(shade(color, 1000), RGB::BLACK);
+        assert_eq!(tint(color, 0), color);
+        assert_eq!(shade(color, 0), color);
+        assert_eq!(tone(color, 0), color);
+        let gray = tone(color, 1000);
+        assert_eq!((gray.red, gray.green), (gray.blue, gray.blue));
+        assert_eq!(gray.red, luminance(color));
+        // amounts past 1000 saturate instead of wrapping
+        assert_eq!(tint(RGB::BLACK, 2000), RGB::WHITE);
+        assert_eq!(shade(color, u16::MAX), RGB::BLACK);
+        assert_eq!(tone(color, 1500), gray);
+    }
+
+    #[test]
+    fn test_oklab() {
+        let (l, a, b) = rgb_to_oklab(RGB::WHITE);
+        assert!((l - 1.0).abs() < 1e-3 && a.abs() < 1e-3 && b.abs() < 1e-3);
+        let (l, a, b) = rgb_to_oklab(RGB::RED);
+        assert!((l - 0.628).abs() < 1e-3 && (a - 0.2249).abs() < 1e-3 && (b - 0.1258).abs() < 1e-3);
+        for &color in [
+            RGB::BLACK,
+            RGB::RED,
+            rgb(0xcc3366),
+            rgb(0x123456),
+            rgb(0x00ff80),
+        ]
+        .iter()
+        {
+            assert_eq!(oklab_to_rgb(rgb_to_oklab(color)), color);
+        }
+    }
+
+    #[test]
+    fn test_gradient_ramp_oklab() {
+        let ramp = gradient_ramp_oklab(RGB::RED, RGB::GREEN, 5);
+        assert_eq!(ramp.len(), 5);
+        assert_eq!((ramp[0], ramp[4]), (RGB::RED, RGB::GREEN));
+        // the srgb midpoint is a dark olive, the oklab one stays bright
+        let muddy = mix(500, RGB::RED, RGB::GREEN);
+        assert!(relative_luminance(ramp[2]) > 2.0 * relative_luminance(muddy));
+        assert!(ramp[2].blue < 50);
+        assert_eq!(gradient_ramp_oklab(RGB::RED, RGB::GREEN, 1), vec![RGB::RED]);
+        assert!(gradient_ramp_oklab(RGB::RED, RGB::GREEN, 0).is_empty());
+    }
+
+    #[test]
+    fn test_ciede2000() {
+        // pairs and expected differences from Sharma et al. (2005), table 1
+        let pairs = [
+            ((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+            ((50.0, 3.1571, -77.2803), (50.0, 0.0, -82.7485), 2.8615),
+            ((50.0, 2.8361, -74.02), (50.0, 0.0, -82.7485), 3.4412),
+            ((50.0, 0.0, 0.0), (50.0, -1.0, 2.0), 2.3669),
+            ((50.0, -1.0, 2.0), (50.0, 0.0, 0.0), 2.3669),
+            ((50.0, 2.49, -0.001), (50.0, -2.49, 0.0009), 7.1792),
+            ((50.0, 2.49, -0.001), (50.0, -2.49, 0.0011), 7.2195),
+            ((50.0, 2.5, 0.0), (73.0, 25.0, -18.0), 27.1492),
+            ((50.0, 2.5, 0.0), (50.0, 3.1736, 0.5854), 1.0),
+            (
+                (60.2574, -34.0099, 36.2677),
+                (60.4626, -34.1751, 39.4387),
+                1.2644,
+            ),
+            (
+                (63.0109, -31.0961, -5.8663),
+                (62.8187, -29.7946, -4.0864),
+                1.263,
+            ),
+            (
+                (90.8027, -2.0831, 1.441),
+                (91.1528, -1.6435, 0.0447),
+                1.4441,
+            ),
+            (
+                (90.9257, -0.5406, -0.9208),
+                (88.6381, -0.8985, -0.7239),
+                1.5381,
+            ),
+        ];
+        for &(a, b, expected) in pairs.iter() {
+            let d = ciede2000(a, b);
+            assert!(
+                (d - expected).abs() < 1e-4,
+                "{:?} {:?} {} != {}",
+                a,
+                b,
+                d,
+                expected
+            );
+        }
+        assert_eq!(delta_e_2000(rgb(0xcc3366), rgb(0xcc3366)), 0.0);
+        assert!(delta_e_2000(RGB::BLACK, RGB::WHITE) > 99.0);
+    }
+
+    #[test]
+    fn test_mix_f() {
+        let pairs = [
+            (rgb(0xff0000), rgb(0x01fe00)),
+            (rgb(0x000000), rgb(0xfefefe)),
+            (rgb(0xcc3366), rgb(0x3265ca)),
+            (rgb(0x000000), rgb(0xffffff)),
+            (rgb(0xcc3366), rgb(0x3366cc)),
+        ];
+        for &(a, b) in pairs.iter() {
+            assert_eq!(mix_f(0.5, a, b), mix(500, a, b));
+            assert_eq!(mix_f(0.0, a, b), a);
+            assert_eq!(mix_f(1.0, a, b), b);
+            assert_eq!(mix_f(-1.0, a, b), a);
+            assert_eq!(mix_f(2.0, a, b), b);
+        }
+        assert_eq!(mix_f(0.5, rgb(0x000000), rgb(0xffffff)), rgb(0x808080));
+    }
+
+    #[test]
+    fn test_mix_linear() {
+        let (red, green) = (rgb(0xff0000), rgb(0x00ff00));
+        let naive = mix(500, red, green);
+        let linear = mix_linear(500, red, green);
+        assert_eq!(linear, RGB::new(188, 188, 0));
+        assert!(relative_luminance(linear) > relative_luminance(naive));
+        assert_eq!(mix_linear(0, red, green), red);
+        assert_eq!(mix_linear(1000, red, green), green);
     }
 
     #[test]
     fn test_palettes() {
         for hue in (0..360).step_by(30) {
             let color: HWB = (hue * 10, 0, 0);
-            palette(color);
+            palette(color).unwrap();
         }
     }
 
     #[test]
     fn test_hue_palette() {
-        hue_palette();
+        hue_palette().unwrap();
+    }
+
+    #[test]
+    fn test_palette_sized_empty() {
+        let pixels = palette_sized((0, 0, 0), 0, 16);
+        assert_eq!((pixels.width, pixels.height), (0, 0));
+        let pixels = palette_sized((0, 0, 0), 4, 0);
+        assert_eq!((pixels.width, pixels.height), (0, 0));
+    }
+
+    #[test]
+    fn test_palette_grid() {
+        let grid = palette_grid((1200, 0, 0), 5);
+        assert_eq!(grid.len(), 5);
+        assert!(grid.iter().all(|row| row.len() == 5));
+        assert_eq!(grid[0][0], rgb(0x00ff00));
+        assert_eq!(grid[0][4], rgb(0x000000));
+        assert_eq!(grid[4][0], rgb(0xffffff));
+        assert_eq!(grid[4][4], hwb_to_rgb((1200, 1000, 1000)));
+        assert_eq!(grid[4][4], RGB::new(127, 127, 127));
+        assert!(palette_grid((0, 0, 0), 0).is_empty());
+        assert_eq!(palette_grid((0, 0, 0), 1), vec![vec![rgb(0xff0000)]]);
+    }
+
+    #[test]
+    fn test_palette_sized() {
+        let pixels = palette_sized((0, 0, 0), 16, 4);
+        assert_eq!((pixels.width, pixels.height), (64, 64));
+        assert_eq!(pixels.get(0, 0), rgb(0xff0000));
+        assert_eq!(pixels.get(3, 3), rgb(0xff0000));
+        assert_ne!(pixels.get(4, 0), rgb(0xff0000));
+        assert_eq!(pixels.get(63, 0), rgb(0x000000));
+        assert_eq!(pixels.get(0, 63), rgb(0xffffff));
+    }
+
+    #[test]
+    fn test_saturation_grid() {
+        let frames = saturation_grid(15, 4, 15);
+        assert_eq!(frames.len(), 5);
+        for frame in &frames {
+            assert_eq!((frame.width, frame.height), (24 * 16, 16 * 16));
+        }
+        // no saturation is gray, full saturation at mid lightness is the pure hue
+        assert!(frames[0]
+            .pixels()
+            .all(|(_, _, c)| c.red == c.green && c.green == c.blue));
+        let grid = saturation_grid(120, 2, 2);
+        assert_eq!(grid[2].get(0, 16), RGB::RED);
+        assert_eq!(grid[2].get(16, 16), RGB::GREEN);
+        assert_eq!(grid[2].get(32, 16), RGB::BLUE);
+        assert_eq!(grid[1].get(0, 0), RGB::BLACK);
+        assert_eq!(grid[1].get(0, 32), RGB::WHITE);
+    }
+
+    #[test]
+    fn test_mandelbrot() {
+        let pixels = mandelbrot(70, 50, 64);
+        assert_eq!((pixels.width, pixels.height), (70, 50));
+        // the origin sits at x = 35 + 0.75 / 0.05
+        let near_origin = (45..56)
+            .flat_map(|x| (20..30).map(move |y| (x, y)))
+            .filter(|&(x, y)| pixels.get(x, y) == RGB::BLACK)
+            .count();
+        assert!(near_origin > 100);
+        let corners = [(0, 0), (69, 0), (0, 49), (69, 49)];
+        assert!(corners.iter().all(|&(x, y)| pixels.get(x, y) != RGB::BLACK));
+        let colorful = pixels
+            .pixels()
+            .filter(|(_, _, c)| max(*c) - min(*c) == 255)
+            .count();
+        assert!(colorful > 70 * 50 / 2);
+
+        let zoomed = mandelbrot_view(8, 8, 64, (0.0, 0.0), 0.5);
+        assert!(zoomed.pixels().all(|(_, _, c)| c == RGB::BLACK));
+
+        // just past the cusp at 0.25 escapes after millions of iterations
+        let cusp = mandelbrot_view(1, 1, 10_000_000, (0.25 + 1e-12, 0.0), 1e-15);
+        assert_ne!(cusp.get(0, 0), RGB::BLACK);
+    }
+
+    #[test]
+    fn test_value_noise() {
+        let ramp = [RGB::BLUE, RGB::GREEN, RGB::WHITE];
+        let render = |seed| {
+            let mut pixels = Pixels::new(32, 24);
+            pixels.value_noise(seed, 6.0, &ramp);
+            pixels
+        };
+        let a = render(7);
+        assert_eq!(a.data, render(7).data);
+        assert_ne!(a.data, render(8).data);
+        // neighbouring pixels change gradually
+        assert!(
+            (0..31).all(|x| (a.get(x, 5).green as i32 - a.get(x + 1, 5).green as i32).abs() < 128)
+        );
+
+        let mut single = Pixels::new(4, 4);
+        single.value_noise(1, 2.0, &[RGB::RED]);
+        assert!(single.pixels().all(|(_, _, c)| c == RGB::RED));
+        single.value_noise(1, 2.0, &[]);
+        assert!(single.pixels().all(|(_, _, c)| c == RGB::RED));
+    }
+
+    #[test]
+    fn test_palette_to() {
+        let dir = std::env::temp_dir()
+            .join("colors_palette_to")
+            .join("nested");
+        palette_to((1200, 0, 0), &dir).unwrap();
+        hue_palette_to(&dir).unwrap();
+        assert!(dir.join("palette120.png").exists());
+        assert!(dir.join("hue_palette.png").exists());
+        fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_palette_to_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        let dir = std::env::temp_dir().join(OsStr::from_bytes(b"colors_palette_\xff"));
+        palette_to((0, 0, 0), &dir).unwrap();
+        assert!(dir.join("palette0.png").exists());
+        fs::remove_dir_all(&dir).unwrap();
     }
 }